@@ -0,0 +1,40 @@
+use std::time::{Duration, Instant};
+
+use ::IntComp::{IntComp, instruction::Status};
+
+const BOOST: &str = include_str!("../../day_9/input");
+const RUNS: u32 = 20;
+
+// Runs the day 9 BOOST program in sensor boost mode, with and without the decode cache.
+fn main() {
+    let program: Vec<i64> = BOOST.trim().split(',').map(|word| word.trim().parse().unwrap()).collect();
+
+    let uncached = measure(&program, false);
+    let cached = measure(&program, true);
+
+    println!("boost without decode cache: {:?} per run", uncached);
+    println!("boost with decode cache:    {:?} per run", cached);
+    println!("speedup: {:.2}x", uncached.as_secs_f64() / cached.as_secs_f64());
+}
+
+fn measure(program: &[i64], cache: bool) -> Duration {
+    let mut int_comp = IntComp::new(program);
+    int_comp.set_decode_cache(cache);
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        int_comp.reset();
+        int_comp.push_input(2);
+        let mut outputs = Vec::new();
+        loop {
+            match int_comp.run().unwrap() {
+                Status::Outputed(value) => outputs.push(value),
+                Status::Halted => break,
+                status => panic!("unexpected {:?}", status),
+            }
+        }
+        assert_eq!(outputs.len(), 1);
+    }
+
+    start.elapsed() / RUNS
+}
@@ -0,0 +1,124 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{IntComp, error::IntCompError, instruction::Status};
+
+const NEWLINE: i64 = 10;
+
+/// The character codes of `line` followed by a newline.
+pub fn encode_line(line: &str) -> impl Iterator<Item = i64> + '_ {
+    line.chars().map(|char| char as i64).chain([NEWLINE])
+}
+
+/// What the program printed since the last input: codes below 128 as text, everything else as values.
+#[derive(PartialEq, Eq, Clone, Default)]
+#[derive(Debug)]
+pub struct AsciiOutput {
+    pub text: String,
+    pub values: Vec<i64>,
+}
+
+/// Talks to programs that read and write text one character code at a time.
+pub struct AsciiComp {
+    int_comp: IntComp,
+}
+
+impl AsciiComp {
+    pub fn new(int_comp: IntComp) -> Self {
+        AsciiComp { int_comp }
+    }
+
+    pub fn int_comp(&self) -> &IntComp {
+        &self.int_comp
+    }
+
+    pub fn int_comp_mut(&mut self) -> &mut IntComp {
+        &mut self.int_comp
+    }
+
+    pub fn into_inner(self) -> IntComp {
+        self.int_comp
+    }
+
+    pub fn send_line(&mut self, line: &str) {
+        self.int_comp.extend_input(encode_line(line));
+    }
+
+    /// Runs until the program halts or needs more input, collecting everything it prints.
+    pub fn run(&mut self) -> Result<(Status, AsciiOutput), IntCompError> {
+        let mut output = AsciiOutput::default();
+        loop {
+            match self.int_comp.run()? {
+                Status::Outputed(value @ 0..=127) => output.text.push(value as u8 as char),
+                Status::Outputed(value) => output.values.push(value),
+                status => return Ok((status, output)),
+            }
+        }
+    }
+
+    /// Prints the program's text to `output` and answers each input request with a line read
+    /// from `input`, until the program halts, fails or `input` ends. Values that are not text are
+    /// printed on their own lines after the text.
+    pub fn interact(&mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        loop {
+            match self.run() {
+                Ok((status, printed)) => {
+                    write!(output, "{}", printed.text)?;
+                    for value in printed.values {
+                        writeln!(output, "{}", value)?;
+                    }
+                    if status != Status::RequestedInput {
+                        return output.flush();
+                    }
+                },
+                Err(error) => {
+                    writeln!(output, "Error: {}", error)?;
+                    return output.flush();
+                },
+            }
+            output.flush()?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            self.send_line(line.trim_end_matches(['\n', '\r']));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Prints "Hi", echoes one character and prints 1000.
+    const ECHO: [i64; 14] = [104, 72, 104, 105, 104, 10, 3, 13, 4, 13, 104, 1000, 99, 0];
+
+    #[test]
+    fn encode_line_appends_newline() {
+        assert_eq!(encode_line("ab").collect::<Vec<i64>>(), vec![97, 98, 10]);
+    }
+
+    #[test]
+    fn run_separates_text_from_values() {
+        let mut ascii = AsciiComp::new(IntComp::new(&ECHO));
+
+        let (status, output) = ascii.run().unwrap();
+        assert_eq!(status, Status::RequestedInput);
+        assert_eq!(output.text, "Hi\n");
+
+        ascii.send_line("x");
+        let (status, output) = ascii.run().unwrap();
+        assert_eq!(status, Status::Halted);
+        assert_eq!(output, AsciiOutput { text: "x".to_string(), values: vec![1000] });
+    }
+
+    #[test]
+    fn interact_answers_input_with_lines() {
+        let mut ascii = AsciiComp::new(IntComp::new(&ECHO));
+        let mut output = Vec::new();
+
+        ascii.interact("x\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "Hi\nx1000\n");
+    }
+}
@@ -0,0 +1,246 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+use crate::instruction::{Instruction, param::ParamMode};
+
+const OPCODES: [i64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+#[derive(PartialEq, Eq, Clone)]
+#[derive(Debug)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+enum Item<'a> {
+    Instruction { line: usize, opcode: i64, operands: Vec<&'a str> },
+    Data { line: usize, values: Vec<&'a str> },
+}
+
+/// Assembles source written with the disassembler mnemonics into a program.
+///
+/// Operands are `#expr` (immediate), `[expr]` (position) or `rb+expr` (relative), where `expr` is a number,
+/// a label or an `.equ` constant, optionally followed by `+N` or `-N`. Lines may start with `label:`,
+/// `.data a, b, ...` (or `DATA a`) emits raw words and `;` starts a comment.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+    let mut symbols: HashMap<String, i64> = HashMap::new();
+    let mut items = Vec::new();
+    let mut address = 0i64;
+
+    for (number, line) in source.lines().enumerate() {
+        let number = number + 1;
+        let error = |message: String| AsmError { line: number, message };
+        let mut line = line.split(';').next().unwrap_or_default().trim();
+
+        if let Some((label, rest)) = line.split_once(':') {
+            let label = label.trim();
+            if !is_symbol(label) {
+                return Err(error(format!("{} is not a valid label", label)));
+            }
+            if symbols.insert(label.to_string(), address).is_some() {
+                return Err(error(format!("{} is defined twice", label)));
+            }
+            line = rest.trim();
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let (head, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let operands: Vec<&str> = rest.split(',').map(str::trim).filter(|operand| !operand.is_empty()).collect();
+
+        match head.to_ascii_uppercase().as_str() {
+            ".EQU" => {
+                let (name, value) = rest.trim().split_once(char::is_whitespace)
+                    .ok_or_else(|| error(String::from(".equ expects a name and a value")))?;
+                if !is_symbol(name) {
+                    return Err(error(format!("{} is not a valid name", name)));
+                }
+                let value = evaluate(value.trim(), &symbols).map_err(error)?;
+                if symbols.insert(name.to_string(), value).is_some() {
+                    return Err(error(format!("{} is defined twice", name)));
+                }
+            },
+            ".DATA" | "DATA" => {
+                address += operands.len() as i64;
+                items.push(Item::Data { line: number, values: operands });
+            },
+            mnemonic => {
+                let (opcode, param_count) = lookup(mnemonic)
+                    .ok_or_else(|| error(format!("{} is not a known mnemonic", head)))?;
+                if operands.len() != param_count {
+                    return Err(error(format!("{} takes {} operands, got {}", head, param_count, operands.len())));
+                }
+
+                address += 1 + param_count as i64;
+                items.push(Item::Instruction { line: number, opcode, operands });
+            }
+        }
+    }
+
+    let mut program = Vec::new();
+    for item in items {
+        match item {
+            Item::Instruction { line, opcode, operands } => {
+                let error = |message: String| AsmError { line, message };
+                let mut word = opcode;
+                let mut values = Vec::new();
+                let mut scale = 100;
+                for operand in operands {
+                    let (mode, value) = parse_operand(operand, &symbols).map_err(error)?;
+                    word += scale * match mode {
+                        ParamMode::Position => 0,
+                        ParamMode::Immediate => 1,
+                        ParamMode::Relative => 2,
+                    };
+                    scale *= 10;
+                    values.push(value);
+                }
+                program.push(word);
+                program.append(&mut values);
+            },
+            Item::Data { line, values } => {
+                for value in values {
+                    program.push(evaluate(value, &symbols).map_err(|message| AsmError { line, message })?);
+                }
+            }
+        }
+    }
+
+    Ok(program)
+}
+
+fn lookup(mnemonic: &str) -> Option<(i64, usize)> {
+    OPCODES.iter().find_map(|&opcode| {
+        let inst = Instruction::new(0, &opcode).ok()?;
+        let param_count = inst.opcode().map(|oc| oc.param_count as usize).unwrap_or(0);
+
+        (inst.mnemonic() == mnemonic).then_some((opcode, param_count))
+    })
+}
+
+fn parse_operand(operand: &str, symbols: &HashMap<String, i64>) -> Result<(ParamMode, i64), String> {
+    if let Some(expr) = operand.strip_prefix('#') {
+        return Ok((ParamMode::Immediate, evaluate(expr, symbols)?));
+    }
+    if let Some(expr) = operand.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+        return Ok((ParamMode::Position, evaluate(expr, symbols)?));
+    }
+    if let Some(offset) = operand.get(..2).filter(|prefix| prefix.eq_ignore_ascii_case("rb")).map(|_| operand[2..].trim()) {
+        if offset.is_empty() {
+            return Ok((ParamMode::Relative, 0));
+        }
+        if offset.starts_with(['+', '-']) {
+            return Ok((ParamMode::Relative, evaluate(offset, symbols)?));
+        }
+    }
+
+    Err(format!("{} is not a valid operand, expected #imm, [pos] or rb+N", operand))
+}
+
+fn evaluate(expr: &str, symbols: &HashMap<String, i64>) -> Result<i64, String> {
+    let expr = expr.trim();
+    let (mut sign, mut rest) = match expr.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, expr.strip_prefix('+').unwrap_or(expr)),
+    };
+    let mut total = 0i64;
+
+    loop {
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        let term = rest[..end].trim();
+        if term.is_empty() {
+            return Err(format!("{} is not a valid expression", expr));
+        }
+        total = resolve(term, symbols)?.checked_mul(sign)
+            .and_then(|value| total.checked_add(value))
+            .ok_or_else(|| format!("{} overflows", expr))?;
+
+        if end == rest.len() {
+            break Ok(total);
+        }
+        sign = if rest[end..].starts_with('-') { -1 } else { 1 };
+        rest = &rest[end + 1..];
+    }
+}
+
+fn resolve(term: &str, symbols: &HashMap<String, i64>) -> Result<i64, String> {
+    if let Ok(value) = term.parse::<i64>() {
+        return Ok(value);
+    }
+
+    symbols.get(term).copied().ok_or_else(|| format!("{} is not defined", term))
+}
+
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(char) if char.is_ascii_alphabetic() || char == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm::disassemble;
+
+    #[test]
+    fn assemble_encodes_modes() {
+        let program = assemble("ADD rb+4, #5, [6]\nARB #-3\nJT rb-5, #0\nHLT").unwrap();
+
+        assert_eq!(program, vec![1201, 4, 5, 6, 109, -3, 1205, -5, 0, 99]);
+    }
+
+    #[test]
+    fn assemble_resolves_labels_and_constants() {
+        let source = "
+            .equ offset 2
+            start:  IN rb+offset        ; read into the frame
+                    JT rb+offset, #end
+                    JF #0, #start
+            end:    OUT [value + 1]
+                    hlt
+            value:  .data 7, 8
+        ";
+
+        let program = assemble(source).unwrap();
+
+        assert_eq!(program, vec![203, 2, 1205, 2, 8, 1106, 0, 0, 4, 12, 99, 7, 8]);
+    }
+
+    #[test]
+    fn assemble_reads_disassembly() {
+        let program = vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0];
+        let source: Vec<String> = disassemble(&program).into_iter().map(|line| line.text).collect();
+
+        assert_eq!(assemble(&source.join("\n")).unwrap(), program);
+    }
+
+    #[test]
+    fn assemble_reports_undefined_symbol() {
+        let error = assemble("OUT #1\nJT #1, #nowhere").unwrap_err();
+
+        assert_eq!(error, AsmError { line: 2, message: String::from("nowhere is not defined") });
+    }
+
+    #[test]
+    fn assemble_reports_operand_count() {
+        let error = assemble("ADD #1, #2").unwrap_err();
+
+        assert_eq!(error.line, 1);
+    }
+
+    #[test]
+    fn assemble_rejects_bad_operands_without_panicking() {
+        assert_eq!(assemble("OUT €x").unwrap_err().line, 1);
+
+        let error = assemble(".data 9223372036854775807+1").unwrap_err();
+        assert_eq!(error.message, "9223372036854775807+1 overflows");
+    }
+}
@@ -0,0 +1,291 @@
+use std::{collections::VecDeque, env, fmt::Display, io::{self, BufRead, Write}, path::Path, process};
+
+use ::IntComp::{IntComp, ascii::encode_line, cfg::ControlFlowGraph, disasm::disassemble, instruction::Status, io::FnIo, lint::lint, loader, trace::WriteTracer,
+    transcript::{self, Transcript}};
+
+const USAGE: &str = "\
+Usage: intcomp <command> <program> [options]
+
+Commands:
+  run <program> [INPUT...]       run the program and print its outputs
+  trace <program> [INPUT...]     like run, tracing every instruction to stderr
+  profile <program> [INPUT...]   run the program and print a profile instead of its outputs
+  disasm <program>               print the disassembly
+  cfg <program>                  print the control flow graph in Graphviz DOT format
+  lint <program>                 report suspicious code reachable from address 0
+  patch <program> ADDR=VALUE...  print the program with the given cells replaced
+  replay <program> TRANSCRIPT    rerun a recorded session and check it behaves the same
+
+Options:
+  --input-file PATH    read inputs from PATH before the ones given as arguments
+  --stdin              read further inputs from stdin, one or more per line
+  --ascii              print outputs below 128 as characters and send stdin lines as text
+  --patch ADDR=VALUE   replace a cell before running (repeatable)
+  --peek ADDR          print a cell after the program halts (repeatable)
+  --csv                print the profile as CSV
+  --record PATH        save every input and output of the run to PATH
+
+Exit codes: 0 halted, 1 machine error, 2 bad usage, 3 I/O error on the program, input
+or output, 4 program still waiting for input, 5 replay diverged from the transcript,
+6 lint found problems";
+
+const EXIT_MACHINE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_IO: i32 = 3;
+const EXIT_STARVED: i32 = 4;
+const EXIT_DIVERGED: i32 = 5;
+const EXIT_LINTS: i32 = 6;
+
+/// Highest address commands that print the image may patch, as they grow it to that size.
+const MAX_IMAGE_PATCH: usize = 1 << 20;
+
+struct Failure {
+    code: i32,
+    message: String,
+}
+
+fn fail(code: i32, message: impl Display) -> Failure {
+    Failure { code, message: message.to_string() }
+}
+
+#[derive(Default)]
+struct Options {
+    program: String,
+    inputs: Vec<i64>,
+    stdin: bool,
+    ascii: bool,
+    patches: Vec<(usize, i64)>,
+    peeks: Vec<usize>,
+    csv: bool,
+    record: Option<String>,
+    transcript: Option<String>,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(failure) = dispatch(&args) {
+        // Ignore errors here, stderr may be the closed pipe the failure is about.
+        let _ = if failure.code == EXIT_USAGE {
+            writeln!(io::stderr(), "{}\n\n{}", failure.message, USAGE)
+        } else {
+            writeln!(io::stderr(), "{}", failure.message)
+        };
+        process::exit(failure.code);
+    }
+}
+
+fn dispatch(args: &[String]) -> Result<(), Failure> {
+    let (command, args) = args.split_first().ok_or_else(|| fail(EXIT_USAGE, "missing command"))?;
+    let options = parse_options(command, args)?;
+    let mut program = loader::load_program(Path::new(&options.program))
+        .map_err(|error| fail(EXIT_IO, format!("{}: {}", options.program, error)))?;
+    // Commands that run the program poke patches past the image into sparse memory instead.
+    let runs = ["run", "trace", "profile", "replay"].contains(&command.as_str());
+    for &(address, value) in &options.patches {
+        if address < program.len() {
+            program[address] = value;
+        } else if !runs {
+            if address > MAX_IMAGE_PATCH {
+                return Err(fail(EXIT_USAGE, format!("cannot patch {}, {} only patches the image up to {}",
+                    address, command, MAX_IMAGE_PATCH)));
+            }
+            program.resize(address + 1, 0);
+            program[address] = value;
+        }
+    }
+
+    match command.as_str() {
+        "run" => run(&program, &options, false),
+        "trace" => run(&program, &options, true),
+        "profile" => profile(&program, &options),
+        "replay" => replay(&program, &options),
+        "disasm" => {
+            let mut stdout = io::stdout().lock();
+            disassemble(&program).iter()
+                .try_for_each(|line| writeln!(stdout, "{}", line))
+                .map_err(|error| fail(EXIT_IO, error))
+        },
+        "cfg" => ControlFlowGraph::build(&program).write_dot(&program, &mut io::stdout().lock())
+            .map_err(|error| fail(EXIT_IO, error)),
+        "lint" => {
+            let lints = lint(&program);
+            let mut stdout = io::stdout().lock();
+            lints.iter()
+                .try_for_each(|lint| writeln!(stdout, "{}", lint))
+                .map_err(|error| fail(EXIT_IO, error))?;
+            match lints.len() {
+                0 => Ok(()),
+                count => Err(fail(EXIT_LINTS, format!("{} problems found", count))),
+            }
+        },
+        "patch" => {
+            let words: Vec<String> = program.iter().map(|word| word.to_string()).collect();
+            writeln!(io::stdout(), "{}", words.join(",")).map_err(|error| fail(EXIT_IO, error))
+        },
+        _ => unreachable!("parse_options accepts known commands only"),
+    }
+}
+
+fn parse_options(command: &str, args: &[String]) -> Result<Options, Failure> {
+    if !["run", "trace", "profile", "disasm", "cfg", "lint", "patch", "replay"].contains(&command) {
+        return Err(fail(EXIT_USAGE, format!("unknown command {}", command)));
+    }
+
+    let mut options = Options::default();
+    let mut file_inputs = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| fail(EXIT_USAGE, format!("{} needs a value", name)));
+        match arg.as_str() {
+            "--input-file" => {
+                let path = value(arg)?;
+                file_inputs.extend(loader::load_program(Path::new(path))
+                    .map_err(|error| fail(EXIT_IO, format!("{}: {}", path, error)))?);
+            },
+            "--stdin" => options.stdin = true,
+            "--ascii" => options.ascii = true,
+            "--csv" => options.csv = true,
+            "--record" => options.record = Some(value(arg)?.clone()),
+            "--patch" => options.patches.push(parse_patch(value(arg)?)?),
+            "--peek" => options.peeks.push(parse_number(value(arg)?)?),
+            flag if flag.starts_with("--") => return Err(fail(EXIT_USAGE, format!("unknown option {}", flag))),
+            _ if options.program.is_empty() => options.program = arg.clone(),
+            _ if command == "patch" => options.patches.push(parse_patch(arg)?),
+            _ if command == "replay" && options.transcript.is_none() => options.transcript = Some(arg.clone()),
+            _ => options.inputs.push(parse_number(arg)?),
+        }
+    }
+
+    if options.program.is_empty() {
+        return Err(fail(EXIT_USAGE, "missing program"));
+    }
+    if command == "replay" && options.transcript.is_none() {
+        return Err(fail(EXIT_USAGE, "missing transcript"));
+    }
+    file_inputs.append(&mut options.inputs);
+    options.inputs = file_inputs;
+
+    Ok(options)
+}
+
+fn parse_patch(patch: &str) -> Result<(usize, i64), Failure> {
+    let (address, value) = patch.split_once('=')
+        .ok_or_else(|| fail(EXIT_USAGE, format!("{} is not ADDR=VALUE", patch)))?;
+
+    Ok((parse_number(address)?, parse_number(value)?))
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, Failure> {
+    text.trim().parse().map_err(|_| fail(EXIT_USAGE, format!("{} is not a number", text)))
+}
+
+/// Loads the program with all patches applied, including those past its end.
+fn machine(program: &[i64], options: &Options) -> IntComp {
+    let mut int_comp = IntComp::new(program);
+    for &(address, value) in &options.patches {
+        int_comp.poke(address, value);
+    }
+
+    int_comp
+}
+
+fn run(program: &[i64], options: &Options, trace: bool) -> Result<(), Failure> {
+    let mut int_comp = machine(program, options);
+    if trace {
+        int_comp.set_tracer(WriteTracer::new(io::stderr()));
+    }
+    if options.record.is_some() {
+        int_comp.start_recording();
+    }
+
+    let stdout = io::stdout();
+    let ascii = options.ascii;
+    let mut write_error = None;
+    let status = execute(&mut int_comp, options, |value| {
+        let mut stdout = stdout.lock();
+        let written = match value {
+            0..=127 if ascii => write!(stdout, "{}", value as u8 as char),
+            _ => writeln!(stdout, "{}", value),
+        };
+        if let Err(error) = written {
+            write_error.get_or_insert(error);
+        }
+    });
+    if let Some(error) = write_error {
+        return Err(fail(EXIT_IO, error));
+    }
+    if let Some(error) = int_comp.take_tracer().and_then(|tracer| tracer.error().map(|error| error.to_string())) {
+        return Err(fail(EXIT_IO, format!("trace: {}", error)));
+    }
+    // Save the transcript even when the run failed, that is when it is needed most.
+    if let (Some(path), Some(transcript)) = (&options.record, int_comp.take_transcript()) {
+        transcript.save(Path::new(path)).map_err(|error| fail(EXIT_IO, format!("{}: {}", path, error)))?;
+    }
+    status?;
+
+    let mut stdout = stdout.lock();
+    options.peeks.iter()
+        .try_for_each(|&address| writeln!(stdout, "[{}] = {}", address, int_comp.peek(address)))
+        .map_err(|error| fail(EXIT_IO, error))
+}
+
+fn profile(program: &[i64], options: &Options) -> Result<(), Failure> {
+    let mut int_comp = machine(program, options);
+    int_comp.enable_profiling();
+    execute(&mut int_comp, options, |_| {})?;
+
+    // Hot spots are disassembled from the loaded program, as the code may have modified itself.
+    let profile = int_comp.profile().expect("profiling is enabled");
+    let mut stdout = io::stdout().lock();
+    let written = if options.csv {
+        profile.write_csv(program, &mut stdout)
+    } else {
+        profile.write_report(program, &mut stdout)
+    };
+
+    written.map_err(|error| fail(EXIT_IO, error))
+}
+
+fn replay(program: &[i64], options: &Options) -> Result<(), Failure> {
+    let path = options.transcript.as_ref().expect("parse_options checks the transcript");
+    let recorded = Transcript::load(Path::new(path)).map_err(|error| fail(EXIT_IO, format!("{}: {}", path, error)))?;
+
+    match transcript::replay(&mut machine(program, options), &recorded) {
+        Ok(_) => writeln!(io::stdout(), "replayed {} events over {} instructions", recorded.events.len(), recorded.executed)
+            .map_err(|error| fail(EXIT_IO, error)),
+        Err(divergence) => Err(fail(EXIT_DIVERGED, divergence)),
+    }
+}
+
+/// Runs to completion, feeding the argument inputs first and then stdin if allowed.
+fn execute(int_comp: &mut IntComp, options: &Options, output: impl FnMut(i64)) -> Result<(), Failure> {
+    let mut inputs: VecDeque<i64> = options.inputs.iter().copied().collect();
+    let mut stdin_error = None;
+    let read = || {
+        while inputs.is_empty() && options.stdin && stdin_error.is_none() {
+            let mut line = String::new();
+            match io::stdin().lock().read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) if options.ascii => inputs.extend(encode_line(line.trim_end_matches(['\n', '\r']))),
+                Ok(_) => match loader::parse_programs(&line) {
+                    Ok(values) => inputs.extend(values.into_iter().flatten()),
+                    Err(error) => stdin_error = Some(fail(EXIT_IO, format!("stdin: {}", error))),
+                },
+                Err(error) => stdin_error = Some(fail(EXIT_IO, format!("stdin: {}", error))),
+            }
+        }
+        inputs.pop_front()
+    };
+
+    let status = int_comp.run_with_io(&mut FnIo::new(read, output));
+    io::stdout().flush().map_err(|error| fail(EXIT_IO, error))?;
+    if let Some(failure) = stdin_error {
+        return Err(failure);
+    }
+
+    match status {
+        Ok(Status::Halted) => Ok(()),
+        Ok(_) => Err(fail(EXIT_STARVED, format!("program is waiting for input at {}", int_comp.index()))),
+        Err(error) => Err(fail(EXIT_MACHINE, error)),
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use crate::disasm::{DisasmLine, disassemble_at};
+use crate::instruction::{Instruction, param::{Param, ParamMode, Params}};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug)]
+pub enum Edge {
+    /// Execution runs on into the next instruction, or a branch is not taken.
+    Next(usize),
+    /// A branch to an immediate target.
+    Jump(usize),
+    /// A branch whose target is only known at run time.
+    Unknown,
+}
+
+#[derive(PartialEq, Eq, Clone)]
+#[derive(Debug)]
+pub struct Block {
+    pub start: usize,
+    pub lines: Vec<DisasmLine>,
+    pub edges: Vec<Edge>,
+}
+
+impl Block {
+    /// The address after the last instruction of the block.
+    pub fn end(&self) -> usize {
+        self.lines.last().map_or(self.start, |line| line.address + line.words.len())
+    }
+}
+
+/// The basic blocks reachable from address 0, found without running the program.
+/// Code only reached through `Edge::Unknown` jumps or self-modification is not included.
+#[derive(PartialEq, Eq, Clone)]
+#[derive(Debug)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<Block>,
+    /// Reachable addresses that do not decode to an instruction.
+    pub invalid: Vec<usize>,
+}
+
+struct Decoded {
+    len: usize,
+    edges: Vec<Edge>,
+    ends_block: bool,
+}
+
+impl ControlFlowGraph {
+    pub fn build(program: &[i64]) -> Self {
+        let mut instructions = BTreeMap::new();
+        let mut invalid = BTreeSet::new();
+        let mut leaders = BTreeSet::from([0]);
+        let mut pending = vec![0];
+
+        while let Some(address) = pending.pop() {
+            if instructions.contains_key(&address) || invalid.contains(&address) {
+                continue;
+            }
+            let Some(decoded) = decode(program, address) else {
+                invalid.insert(address);
+                continue;
+            };
+
+            for &edge in &decoded.edges {
+                match edge {
+                    Edge::Next(next) if decoded.ends_block => { leaders.insert(next); pending.push(next) },
+                    Edge::Next(next) => pending.push(next),
+                    Edge::Jump(target) => { leaders.insert(target); pending.push(target) },
+                    Edge::Unknown => {},
+                }
+            }
+            instructions.insert(address, decoded);
+        }
+
+        let blocks = leaders.iter()
+            .filter(|start| instructions.contains_key(start))
+            .map(|&start| {
+                let mut lines = Vec::new();
+                let mut address = start;
+                loop {
+                    let decoded = &instructions[&address];
+                    lines.push(disassemble_at(program, address));
+
+                    let next = address + decoded.len;
+                    if decoded.ends_block || leaders.contains(&next) || !instructions.contains_key(&next) {
+                        break Block { start, lines, edges: decoded.edges.clone() };
+                    }
+                    address = next;
+                }
+            })
+            .collect();
+
+        ControlFlowGraph { blocks, invalid: invalid.into_iter().collect() }
+    }
+
+    pub fn block(&self, start: usize) -> Option<&Block> {
+        self.blocks.binary_search_by_key(&start, |block| block.start).ok().map(|index| &self.blocks[index])
+    }
+
+    /// Writes the graph in Graphviz DOT format, one node per block and per invalid address.
+    pub fn write_dot(&self, program: &[i64], output: &mut impl Write) -> io::Result<()> {
+        writeln!(output, "digraph cfg {{")?;
+        writeln!(output, "    node [shape=box, fontname=\"monospace\"];")?;
+
+        for block in &self.blocks {
+            let label: String = block.lines.iter()
+                .map(|line| format!("{}: {}\\l", line.address, line.text))
+                .collect();
+            writeln!(output, "    n{} [label=\"{}\"];", block.start, label)?;
+        }
+        for &address in &self.invalid {
+            let text = match program.get(address) {
+                Some(word) => format!("DATA {}", word),
+                None => "out of range".to_string(),
+            };
+            writeln!(output, "    n{} [label=\"{}: {}\", color=red];", address, address, text)?;
+        }
+        if self.blocks.iter().any(|block| block.edges.contains(&Edge::Unknown)) {
+            writeln!(output, "    unknown [shape=ellipse, label=\"?\"];")?;
+        }
+
+        for block in &self.blocks {
+            for edge in &block.edges {
+                match edge {
+                    Edge::Next(next) => writeln!(output, "    n{} -> n{};", block.start, next)?,
+                    Edge::Jump(target) => writeln!(output, "    n{} -> n{} [label=\"jump\"];", block.start, target)?,
+                    Edge::Unknown => writeln!(output, "    n{} -> unknown [style=dashed];", block.start)?,
+                }
+            }
+        }
+
+        writeln!(output, "}}")
+    }
+}
+
+fn decode(program: &[i64], address: usize) -> Option<Decoded> {
+    let inst = Instruction::new(address, program.get(address)?).ok()?;
+    let params = match inst.opcode() {
+        Some(oc) => Param::decode(program, address + 1, oc)?,
+        None => Params::default(),
+    };
+    let len = params.len() + 1;
+    let next = Edge::Next(address + len);
+
+    let decoded = match inst {
+        Instruction::Halt => Decoded { len, edges: Vec::new(), ends_block: true },
+        Instruction::JumpTrue(_) | Instruction::JumpFalse(_) => {
+            let jumps_on_true = matches!(inst, Instruction::JumpTrue(_));
+            let target = match params[1] {
+                Param { config: ParamMode::Immediate, value, .. } if value >= 0 => Edge::Jump(value as usize),
+                _ => Edge::Unknown,
+            };
+            // An immediate condition decides the branch once and for all, as in `1105,1,target`.
+            let edges = match params[0].config {
+                ParamMode::Immediate if (params[0].value != 0) == jumps_on_true => vec![target],
+                ParamMode::Immediate => vec![next],
+                _ => vec![next, target],
+            };
+            Decoded { len, edges, ends_block: true }
+        },
+        _ => Decoded { len, edges: vec![next], ends_block: false },
+    };
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts [20] down from 3, outputting every value.
+    const COUNTDOWN: [i64; 12] = [4, 20, 1001, 20, -1, 20, 1005, 20, 0, 99, 0, 0];
+
+    fn starts(cfg: &ControlFlowGraph) -> Vec<usize> {
+        cfg.blocks.iter().map(|block| block.start).collect()
+    }
+
+    #[test]
+    fn cfg_splits_loops_into_blocks() {
+        let cfg = ControlFlowGraph::build(&COUNTDOWN);
+
+        assert_eq!(starts(&cfg), vec![0, 9]);
+        assert_eq!(cfg.block(0).unwrap().edges, vec![Edge::Next(9), Edge::Jump(0)]);
+        assert_eq!(cfg.block(0).unwrap().end(), 9);
+        assert_eq!(cfg.block(9).unwrap().edges, vec![]);
+        assert!(cfg.invalid.is_empty());
+    }
+
+    #[test]
+    fn cfg_skips_data_after_unconditional_jump() {
+        let program = vec![1105, 1, 4, 42, 104, 7, 1106, 0, 10, 13, 99];
+        let cfg = ControlFlowGraph::build(&program);
+
+        assert_eq!(starts(&cfg), vec![0, 4, 10]);
+        assert_eq!(cfg.block(4).unwrap().lines.len(), 2);
+        assert_eq!(cfg.block(4).unwrap().edges, vec![Edge::Jump(10)]);
+    }
+
+    #[test]
+    fn cfg_marks_indirect_and_invalid_targets() {
+        let program = vec![3, 7, 1005, 7, 8, 5, 7, 0, 42];
+        let cfg = ControlFlowGraph::build(&program);
+
+        assert_eq!(starts(&cfg), vec![0, 5]);
+        assert_eq!(cfg.block(5).unwrap().edges, vec![Edge::Next(8), Edge::Unknown]);
+        assert_eq!(cfg.invalid, vec![8]);
+    }
+
+    #[test]
+    fn cfg_writes_dot() {
+        let program = vec![3, 7, 1005, 7, 8, 5, 7, 0, 42];
+        let mut output = Vec::new();
+
+        ControlFlowGraph::build(&program).write_dot(&program, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("digraph cfg {"));
+        assert!(output.contains("n0 [label=\"0: IN [7]\\l2: JT [7], #8\\l\"];"));
+        assert!(output.contains("n8 [label=\"8: DATA 42\", color=red];"));
+        assert!(output.contains("n0 -> n8 [label=\"jump\"];"));
+        assert!(output.contains("n5 -> unknown [style=dashed];"));
+    }
+}
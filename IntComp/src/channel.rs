@@ -0,0 +1,244 @@
+use std::{future::Future, pin::Pin, sync::mpsc::{Receiver, Sender, TryRecvError}, task::{Context, Poll}, thread};
+
+use crate::{IntComp, error::IntCompError, instruction::Status, io::IntcodeIo};
+
+/// How many instructions a machine runs before yielding to other tasks.
+const YIELD_STEPS: u64 = 4096;
+
+/// What a machine reads when it wants input and none is queued.
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+#[derive(Debug)]
+pub enum InputPolicy {
+    /// Wait until a value arrives.
+    #[default]
+    Wait,
+    /// Read -1 and carry on, as network nodes polling an empty queue expect.
+    NegativeOne,
+}
+
+/// Drives a machine on its own thread: reads come from a channel and outputs go to another one.
+/// Under `InputPolicy::Wait` reads block, and a closed input channel stops the machine.
+pub struct ChannelIo {
+    input: Receiver<i64>,
+    output: Sender<i64>,
+    policy: InputPolicy,
+}
+
+impl ChannelIo {
+    pub fn new(input: Receiver<i64>, output: Sender<i64>, policy: InputPolicy) -> Self {
+        ChannelIo { input, output, policy }
+    }
+}
+
+impl IntcodeIo for ChannelIo {
+    fn read(&mut self) -> Option<i64> {
+        match self.policy {
+            InputPolicy::Wait => self.input.recv().ok(),
+            InputPolicy::NegativeOne => match self.input.try_recv() {
+                Ok(value) => Some(value),
+                Err(TryRecvError::Empty) => {
+                    thread::yield_now();
+                    Some(-1)
+                },
+                Err(TryRecvError::Disconnected) => None,
+            },
+        }
+    }
+
+    fn write(&mut self, value: i64) {
+        // Nobody listening is not the program's problem; its outputs are dropped.
+        let _ = self.output.send(value);
+    }
+}
+
+/// Source of input for `IntComp::run_async`, independent of any async runtime.
+pub trait AsyncInput {
+    /// `Ready(None)` means no more input will ever arrive.
+    fn poll_read(&mut self, cx: &mut Context<'_>) -> Poll<Option<i64>>;
+}
+
+/// Destination of outputs for `IntComp::run_async`. Writes never wait.
+pub trait AsyncOutput {
+    fn write(&mut self, value: i64);
+}
+
+/// std channels cannot wake a task when a value arrives, so an empty channel asks to be polled
+/// again straight away.
+impl AsyncInput for Receiver<i64> {
+    fn poll_read(&mut self, cx: &mut Context<'_>) -> Poll<Option<i64>> {
+        match self.try_recv() {
+            Ok(value) => Poll::Ready(Some(value)),
+            Err(TryRecvError::Empty) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            },
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+        }
+    }
+}
+
+impl AsyncOutput for Sender<i64> {
+    fn write(&mut self, value: i64) {
+        let _ = self.send(value);
+    }
+}
+
+/// Future returned by `IntComp::run_async`.
+pub struct RunFuture<'a, I, O> {
+    int_comp: &'a mut IntComp,
+    input: &'a mut I,
+    output: &'a mut O,
+    policy: InputPolicy,
+}
+
+impl<I: AsyncInput + Unpin, O: AsyncOutput + Unpin> Future for RunFuture<'_, I, O> {
+    type Output = Result<Status, IntCompError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            match this.int_comp.run_for(YIELD_STEPS)? {
+                Status::Outputed(value) => this.output.write(value),
+                Status::RequestedInput => match (this.input.poll_read(cx), this.policy) {
+                    (Poll::Ready(Some(value)), _) => this.int_comp.push_input(value),
+                    (Poll::Ready(None), _) => return Poll::Ready(Ok(Status::RequestedInput)),
+                    (Poll::Pending, InputPolicy::Wait) => return Poll::Pending,
+                    (Poll::Pending, InputPolicy::NegativeOne) => {
+                        this.int_comp.push_input(-1);
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    },
+                },
+                Status::BudgetExhausted => {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                },
+                status => return Poll::Ready(Ok(status)),
+            }
+        }
+    }
+}
+
+impl IntComp {
+    /// Runs the machine as a future that reads from `input` and writes to `output`. It yields
+    /// whenever input is empty and every few thousand instructions, so many machines can share one
+    /// thread. Finishes when the program halts, or with `Status::RequestedInput` once `input` is
+    /// closed.
+    pub fn run_async<'a, I, O>(&'a mut self, input: &'a mut I, output: &'a mut O, policy: InputPolicy) -> RunFuture<'a, I, O>
+    where I: AsyncInput + Unpin, O: AsyncOutput + Unpin
+    {
+        RunFuture { int_comp: self, input, output, policy }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use std::{sync::mpsc::channel, task::Waker};
+
+    // Outputs twice each input until its input is closed.
+    const DOUBLER: [i64; 12] = [3, 11, 1002, 11, 2, 11, 4, 11, 1105, 1, 0, 0];
+
+    // Reads its address, then forwards packets (x, y) to the next of three nodes with y + 1, until
+    // y reaches 10 and the packet goes to address 255 instead.
+    const NODE: &str = "
+                IN [addr]
+        loop:   IN [x]
+                EQ [x], #-1, [t]
+                JT [t], #loop
+                IN [y]
+                ADD [y], #1, [y]
+                LT [y], #10, [t]
+                JF [t], #done
+                ADD [addr], #1, [dest]
+                EQ [dest], #3, [t]
+                JF [t], #send
+                ADD #0, #0, [dest]
+        send:   OUT [dest]
+                OUT [x]
+                OUT [y]
+                JT #1, #loop
+        done:   OUT #255
+                OUT [x]
+                OUT [y]
+                HLT
+        addr:   .data 0
+        dest:   .data 0
+        x:      .data 0
+        y:      .data 0
+        t:      .data 0
+    ";
+
+    #[test]
+    fn channel_io_connects_threads() {
+        let (to_first, first_input) = channel();
+        let (to_second, second_input) = channel();
+        let (to_main, output) = channel();
+
+        let machines: Vec<_> = [(first_input, to_second), (second_input, to_main)].into_iter()
+            .map(|(input, output)| thread::spawn(move || {
+                IntComp::new(&DOUBLER).run_with_io(&mut ChannelIo::new(input, output, InputPolicy::Wait))
+            }))
+            .collect();
+
+        for value in 1..=3 {
+            to_first.send(value).unwrap();
+        }
+        drop(to_first);
+
+        assert_eq!(output.iter().collect::<Vec<i64>>(), vec![4, 8, 12]);
+        for machine in machines {
+            assert_eq!(machine.join().unwrap().unwrap(), Status::RequestedInput);
+        }
+    }
+
+    #[test]
+    fn async_nodes_exchange_packets() {
+        let program = assemble(NODE).unwrap();
+        let mut machines: Vec<IntComp> = (0..3).map(|_| IntComp::new(&program)).collect();
+        let (mut inboxes, mut inputs, mut outputs, mut outboxes) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        for _ in 0..3 {
+            let (inbox, input) = channel();
+            let (output, outbox) = channel();
+            inboxes.push(inbox);
+            inputs.push(input);
+            outputs.push(output);
+            outboxes.push(outbox);
+        }
+        for (address, inbox) in inboxes.iter().enumerate() {
+            inbox.send(address as i64).unwrap();
+        }
+        inboxes[0].send(42).unwrap();
+        inboxes[0].send(0).unwrap();
+
+        let mut futures: Vec<_> = machines.iter_mut().zip(inputs.iter_mut().zip(outputs.iter_mut()))
+            .map(|(int_comp, (input, output))| Some(int_comp.run_async(input, output, InputPolicy::NegativeOne)))
+            .collect();
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut pending: Vec<Vec<i64>> = vec![Vec::new(); 3];
+        let mut delivered = None;
+
+        while delivered.is_none() {
+            for future in futures.iter_mut() {
+                if let Some(Poll::Ready(result)) = future.as_mut().map(|future| Pin::new(future).poll(&mut cx)) {
+                    assert_eq!(result.unwrap(), Status::Halted);
+                    *future = None;
+                }
+            }
+            for (outbox, packet) in outboxes.iter().zip(pending.iter_mut()) {
+                packet.extend(outbox.try_iter());
+                while packet.len() >= 3 {
+                    let (dest, x, y) = (packet[0], packet[1], packet[2]);
+                    packet.drain(..3);
+                    match dest {
+                        255 => delivered = Some((x, y)),
+                        dest => { inboxes[dest as usize].send(x).unwrap(); inboxes[dest as usize].send(y).unwrap(); },
+                    }
+                }
+            }
+        }
+
+        assert_eq!(delivered, Some((42, 10)));
+    }
+}
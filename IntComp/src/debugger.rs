@@ -0,0 +1,349 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::{self, BufRead, Write};
+
+use crate::{IntComp, disasm::disassemble_at, error::IntCompError, instruction::Status};
+
+#[derive(PartialEq, Clone, Copy)]
+#[derive(Debug)]
+pub enum Stop {
+    Status(Status),
+    Breakpoint(usize),
+    Watchpoint { address: usize, old: i64, new: i64 },
+}
+
+/// Instructions the debugger can step back over unless the machine already keeps a history.
+const HISTORY_LIMIT: usize = 100_000;
+
+/// Most cells `x` prints at once.
+const MAX_DUMP: usize = 1 << 12;
+
+pub struct Debugger {
+    int_comp: IntComp,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeMap<usize, i64>,
+    output: VecDeque<i64>,
+}
+
+impl Debugger {
+    pub fn new(mut int_comp: IntComp) -> Self {
+        if int_comp.history_limit().is_none() {
+            int_comp.set_history_limit(Some(HISTORY_LIMIT));
+        }
+        Debugger { int_comp, breakpoints: BTreeSet::new(), watchpoints: BTreeMap::new(), output: VecDeque::new() }
+    }
+
+    pub fn int_comp(&self) -> &IntComp {
+        &self.int_comp
+    }
+
+    pub fn int_comp_mut(&mut self) -> &mut IntComp {
+        &mut self.int_comp
+    }
+
+    pub fn add_breakpoint(&mut self, address: usize) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: usize) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn add_watchpoint(&mut self, address: usize) {
+        self.watchpoints.insert(address, self.int_comp.peek(address));
+    }
+
+    pub fn remove_watchpoint(&mut self, address: usize) -> bool {
+        self.watchpoints.remove(&address).is_some()
+    }
+
+    pub fn poke(&mut self, address: usize, value: i64) {
+        self.int_comp.poke(address, value);
+        if let Some(seen) = self.watchpoints.get_mut(&address) {
+            *seen = value;
+        }
+    }
+
+    /// Returns the values the program outputted since the last call.
+    pub fn take_output(&mut self) -> Vec<i64> {
+        self.output.drain(..).collect()
+    }
+
+    pub fn step(&mut self) -> Result<Stop, IntCompError> {
+        let status = self.int_comp.step()?;
+        if let Status::Outputed(value) = status {
+            self.output.push_back(value);
+        }
+
+        Ok(self.changed_watchpoint().unwrap_or(Stop::Status(status)))
+    }
+
+    /// Undoes the last executed instruction. Returns `None` when there is no history left.
+    pub fn step_back(&mut self) -> Option<Stop> {
+        if !self.int_comp.step_back() {
+            return None;
+        }
+
+        Some(self.changed_watchpoint().unwrap_or(Stop::Status(self.int_comp.status())))
+    }
+
+    /// Steps back until a breakpoint or watchpoint is hit. Returns `None` when the history runs out first.
+    pub fn reverse_continue(&mut self) -> Option<Stop> {
+        loop {
+            if let stop @ Stop::Watchpoint { .. } = self.step_back()? {
+                break Some(stop);
+            }
+
+            if self.breakpoints.contains(&self.int_comp.index()) {
+                break Some(Stop::Breakpoint(self.int_comp.index()));
+            }
+        }
+    }
+
+    /// Instruction pointer of the last instruction that wrote `address`, as far back as the history reaches.
+    pub fn last_writer(&self, address: usize) -> Option<usize> {
+        self.int_comp.last_writer(address)
+    }
+
+    fn changed_watchpoint(&mut self) -> Option<Stop> {
+        for (&address, seen) in self.watchpoints.iter_mut() {
+            let value = self.int_comp.peek(address);
+            if value != *seen {
+                let old = *seen;
+                *seen = value;
+                return Some(Stop::Watchpoint { address, old, new: value });
+            }
+        }
+
+        None
+    }
+
+    /// Runs until a breakpoint or watchpoint is hit, the program asks for input it does not have, or it halts.
+    pub fn cont(&mut self) -> Result<Stop, IntCompError> {
+        loop {
+            match self.step()? {
+                Stop::Status(Status::Ready) | Stop::Status(Status::Outputed(_)) => {},
+                stop => break Ok(stop),
+            }
+
+            if self.breakpoints.contains(&self.int_comp.index()) {
+                break Ok(Stop::Breakpoint(self.int_comp.index()));
+            }
+        }
+    }
+
+    /// Reads debugger commands line by line from `input` until `quit` or the end of input.
+    pub fn repl(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        writeln!(output, "{}", self.current())?;
+
+        for line in input.lines() {
+            let line = line?;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let args = words.get(1..).unwrap_or_default();
+            // Addresses, lengths and counts must not be negative; commands given bad numbers print the usage.
+            let sizes: Option<Vec<usize>> = args.iter().map(|word| word.parse().ok()).collect();
+            let values: Option<Vec<i64>> = args.iter().map(|word| word.parse().ok()).collect();
+            let address = args.first().and_then(|word| word.parse::<usize>().ok());
+
+            let result = match (words.first().copied(), address) {
+                (None, _) => continue,
+                (Some("q") | Some("quit"), _) => break,
+                (Some("s") | Some("step"), _) if sizes.is_some() => {
+                    let count = address.unwrap_or(1);
+                    let mut stop = Ok(Stop::Status(Status::Ready));
+                    for _ in 0..count {
+                        stop = self.step();
+                        if !matches!(stop, Ok(Stop::Status(Status::Ready)) | Ok(Stop::Status(Status::Outputed(_)))) {
+                            break;
+                        }
+                    }
+                    Some(stop)
+                },
+                (Some("c") | Some("continue"), _) => Some(self.cont()),
+                (Some("sb") | Some("back"), _) if sizes.is_some() => {
+                    let count = address.unwrap_or(1);
+                    let mut stop = None;
+                    for _ in 0..count {
+                        stop = self.step_back();
+                        if !matches!(stop, Some(Stop::Status(_))) {
+                            break;
+                        }
+                    }
+                    if stop.is_none() {
+                        writeln!(output, "No more history")?;
+                    }
+                    stop.map(Ok)
+                },
+                (Some("rc") | Some("rcontinue"), _) => {
+                    let stop = self.reverse_continue();
+                    if stop.is_none() {
+                        writeln!(output, "No more history")?;
+                    }
+                    stop.map(Ok)
+                },
+                (Some("lw") | Some("writer"), Some(address)) => {
+                    match self.last_writer(address) {
+                        Some(writer) => writeln!(output, "{} last written by {}", address, writer)?,
+                        None => writeln!(output, "No write to {} in history", address)?,
+                    }
+                    None
+                },
+                (Some("b") | Some("break"), Some(address)) => { self.add_breakpoint(address); None },
+                (Some("db") | Some("delete"), Some(address)) => { self.remove_breakpoint(address); None },
+                (Some("w") | Some("watch"), Some(address)) => { self.add_watchpoint(address); None },
+                (Some("dw") | Some("unwatch"), Some(address)) => { self.remove_watchpoint(address); None },
+                (Some("i") | Some("input"), _) if values.is_some() => {
+                    self.int_comp.extend_input(values.unwrap_or_default());
+                    None
+                },
+                (Some("poke"), Some(address)) if args.len() == 2 && values.is_some() => {
+                    self.poke(address, values.unwrap_or_default()[1]);
+                    None
+                },
+                (Some("x") | Some("mem"), Some(address)) if sizes.is_some() => {
+                    let len = sizes.unwrap_or_default().get(1).copied().unwrap_or(8).min(MAX_DUMP);
+                    writeln!(output, "{:>5}: {:?}", address, self.int_comp.peek_range(address, len))?;
+                    None
+                },
+                (Some("r") | Some("regs"), _) => {
+                    writeln!(output, "index: {}, relative base: {}, status: {:?}",
+                        self.int_comp.index(), self.int_comp.relative_base(), self.int_comp.status())?;
+                    None
+                },
+                _ => {
+                    writeln!(output, "Commands: s [n], c, sb [n], rc, lw ADDR, b|db ADDR, w|dw ADDR, i VALUE.., poke ADDR VALUE, x ADDR [LEN], r, q")?;
+                    continue;
+                }
+            };
+
+            for value in self.take_output() {
+                writeln!(output, "Output: {}", value)?;
+            }
+            match result {
+                Some(Ok(Stop::Breakpoint(address))) => writeln!(output, "Breakpoint at {}", address)?,
+                Some(Ok(Stop::Watchpoint { address, old, new })) =>
+                    writeln!(output, "Watchpoint {}: {} -> {}", address, old, new)?,
+                Some(Ok(Stop::Status(Status::RequestedInput))) => writeln!(output, "Input requested")?,
+                Some(Ok(Stop::Status(Status::Halted))) => writeln!(output, "Halted")?,
+                Some(Err(error)) => writeln!(output, "Error: {}", error)?,
+                _ => {}
+            }
+            writeln!(output, "{}", self.current())?;
+        }
+
+        Ok(())
+    }
+
+    fn current(&self) -> String {
+        let index = self.int_comp.index();
+        let memory = self.int_comp.peek_range(index, 4);
+        let mut line = disassemble_at(&memory, 0);
+        line.address = index;
+
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts [20] down from 3, outputting every value.
+    const COUNTDOWN: [i64; 12] = [4, 20, 1001, 20, -1, 20, 1005, 20, 0, 99, 0, 0];
+
+    fn countdown() -> Debugger {
+        let mut program = COUNTDOWN.to_vec();
+        program.resize(21, 0);
+        program[20] = 3;
+
+        Debugger::new(IntComp::new(&program))
+    }
+
+    #[test]
+    fn debugger_stops_at_breakpoint() {
+        let mut debugger = countdown();
+        debugger.add_breakpoint(6);
+
+        let stop = debugger.cont().unwrap();
+
+        assert_eq!(stop, Stop::Breakpoint(6));
+        assert_eq!(debugger.take_output(), vec![3]);
+        assert_eq!(debugger.int_comp().peek(20), 2);
+    }
+
+    #[test]
+    fn debugger_stops_at_watchpoint() {
+        let mut debugger = countdown();
+        debugger.add_watchpoint(20);
+
+        let stop = debugger.cont().unwrap();
+
+        assert_eq!(stop, Stop::Watchpoint { address: 20, old: 3, new: 2 });
+        assert_eq!(debugger.int_comp().index(), 6);
+    }
+
+    #[test]
+    fn debugger_continues_to_halt() {
+        let mut debugger = countdown();
+
+        let stop = debugger.cont().unwrap();
+
+        assert_eq!(stop, Stop::Status(Status::Halted));
+        assert_eq!(debugger.take_output(), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn debugger_steps_back_over_writes() {
+        let mut debugger = countdown();
+        debugger.add_breakpoint(6);
+        debugger.cont().unwrap();
+
+        assert_eq!(debugger.step_back(), Some(Stop::Status(Status::Outputed(3))));
+        assert_eq!(debugger.int_comp().index(), 2);
+        assert_eq!(debugger.int_comp().peek(20), 3);
+        assert_eq!(debugger.step_back(), Some(Stop::Status(Status::Ready)));
+        assert_eq!(debugger.int_comp().index(), 0);
+        assert_eq!(debugger.step_back(), None);
+    }
+
+    #[test]
+    fn debugger_reverse_continues_to_breakpoint() {
+        let mut debugger = countdown();
+        debugger.cont().unwrap();
+        debugger.add_breakpoint(2);
+
+        let stop = debugger.reverse_continue();
+
+        assert_eq!(stop, Some(Stop::Breakpoint(2)));
+        assert_eq!(debugger.int_comp().peek(20), 1);
+        assert_eq!(debugger.int_comp().status(), Status::Outputed(1));
+        assert_eq!(debugger.last_writer(20), Some(2));
+    }
+
+    #[test]
+    fn debugger_repl_rejects_negative_numbers() {
+        let mut debugger = countdown();
+        let mut output: Vec<u8> = Vec::new();
+
+        debugger.repl("x -1\nx 0 -1\ns -1\nsb -1\npoke -1 5\ni 1 x\nx 18446744073709551615\nq\n".as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(output.lines().filter(|line| line.starts_with("Commands:")).count(), 6);
+        assert_eq!(debugger.int_comp().index(), 0);
+    }
+
+    #[test]
+    fn debugger_repl_runs_commands() {
+        let mut debugger = countdown();
+        let mut output: Vec<u8> = Vec::new();
+
+        debugger.repl("b 6\nc\npoke 20 1\nx 20 1\nc\nq\n".as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(output.contains("Output: 3\nBreakpoint at 6"));
+        assert!(output.contains("20: [1]"));
+        assert_eq!(lines[lines.len() - 3..lines.len() - 1], ["Output: 1", "Breakpoint at 6"]);
+        assert!(lines[lines.len() - 1].ends_with("JT [20], #0"));
+        assert_eq!(debugger.int_comp().peek(20), 0);
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+
+use crate::instruction::{Instruction, param::{Param, Params}};
+
+#[derive(PartialEq, Eq, Clone)]
+#[derive(Debug)]
+pub struct DisasmLine {
+    pub address: usize,
+    pub words: Vec<i64>,
+    pub text: String,
+}
+
+impl fmt::Display for DisasmLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words: Vec<String> = self.words.iter().map(|word| word.to_string()).collect();
+        write!(f, "{:>5}: {:<32} {}", self.address, words.join(","), self.text)
+    }
+}
+
+pub fn disassemble(program: &[i64]) -> Vec<DisasmLine> {
+    let mut lines = Vec::new();
+    let mut address = 0;
+
+    while address < program.len() {
+        let line = disassemble_at(program, address);
+
+        address += line.words.len();
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Decodes the single instruction at `address`, falling back to a `DATA` line if it does not decode.
+pub fn disassemble_at(program: &[i64], address: usize) -> DisasmLine {
+    let word = program.get(address).copied().unwrap_or_default();
+
+    decode_line(program, address).unwrap_or_else(|| DisasmLine {
+        address,
+        words: vec![word],
+        text: format!("DATA {}", word),
+    })
+}
+
+fn decode_line(program: &[i64], address: usize) -> Option<DisasmLine> {
+    let inst = Instruction::new(address, program.get(address)?).ok()?;
+    let params = match inst.opcode() {
+        Some(oc) => Param::decode(program, address + 1, oc)?,
+        None => Params::default(),
+    };
+
+    let operands: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+    let text = if operands.is_empty() {
+        inst.mnemonic().to_string()
+    } else {
+        format!("{} {}", inst.mnemonic(), operands.join(", "))
+    };
+
+    Some(DisasmLine { address, words: program[address..=address + params.len()].to_vec(), text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(program: &[i64]) -> Vec<String> {
+        disassemble(program).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn disassemble_renders_modes() {
+        let program = vec![1201, 4, 5, 6, 109, -3, 1205, -5, 0, 99];
+
+        assert_eq!(texts(&program), vec!["ADD rb+4, #5, [6]", "ARB #-3", "JT rb-5, #0", "HLT"]);
+    }
+
+    #[test]
+    fn disassemble_tracks_addresses() {
+        let lines = disassemble(&[3, 5, 4, 5, 99, 0]);
+        let addresses: Vec<usize> = lines.iter().map(|line| line.address).collect();
+
+        assert_eq!(addresses, vec![0, 2, 4, 5]);
+        assert_eq!(lines[1].words, vec![4, 5]);
+    }
+
+    #[test]
+    fn disassemble_falls_back_to_data() {
+        let program = vec![42, 301, 1, 1];
+
+        assert_eq!(texts(&program), vec!["DATA 42", "DATA 301", "DATA 1", "DATA 1"]);
+    }
+}
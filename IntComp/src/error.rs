@@ -0,0 +1,97 @@
+use std::{error::Error, fmt, io};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug)]
+pub enum IntCompError {
+    UnknownOpcode { index: usize, word: i64 },
+    BadParamMode { index: usize, word: i64, mode: u8 },
+    WriteToImmediate { index: usize, word: i64 },
+    NegativeAddress { index: usize, word: i64, address: i64 },
+    AddressTooLarge { index: usize, word: i64, address: u64 },
+    IndexOutOfRange { index: usize },
+    Overflow { index: usize, word: i64 },
+    /// An overflow under `Overflow::Promote`; the machine can continue on a wider word type.
+    WordTooNarrow { index: usize, word: i64 },
+    /// An extension handler used a parameter its opcode was not registered with.
+    MissingParam { index: usize, word: i64, param: usize },
+    /// An extension handler returned `RequestedInput` or `BudgetExhausted`.
+    BadExtensionStatus { index: usize, word: i64 },
+}
+
+impl IntCompError {
+    /// Instruction pointer of the instruction that failed.
+    pub fn index(&self) -> usize {
+        match *self {
+            IntCompError::UnknownOpcode { index, .. } => index,
+            IntCompError::BadParamMode { index, .. } => index,
+            IntCompError::WriteToImmediate { index, .. } => index,
+            IntCompError::NegativeAddress { index, .. } => index,
+            IntCompError::AddressTooLarge { index, .. } => index,
+            IntCompError::IndexOutOfRange { index } => index,
+            IntCompError::Overflow { index, .. } => index,
+            IntCompError::WordTooNarrow { index, .. } => index,
+            IntCompError::MissingParam { index, .. } => index,
+            IntCompError::BadExtensionStatus { index, .. } => index,
+        }
+    }
+}
+
+impl fmt::Display for IntCompError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntCompError::UnknownOpcode { index, word } =>
+                write!(f, "{} is not supported opcode (at {})", word, index),
+            IntCompError::BadParamMode { index, word, mode } =>
+                write!(f, "{} is invalid parameter mode in {} (at {})", mode, word, index),
+            IntCompError::WriteToImmediate { index, word } =>
+                write!(f, "{} writes to immediate parameter (at {})", word, index),
+            IntCompError::NegativeAddress { index, word, address } =>
+                write!(f, "{} accesses negative address {} (at {})", word, address, index),
+            IntCompError::AddressTooLarge { index, word, address } =>
+                write!(f, "{} accesses address {} past the memory limit (at {})", word, address, index),
+            IntCompError::IndexOutOfRange { index } =>
+                write!(f, "instruction missing (at {})", index),
+            IntCompError::Overflow { index, word } =>
+                write!(f, "{} overflows its word type (at {})", word, index),
+            IntCompError::WordTooNarrow { index, word } =>
+                write!(f, "{} needs a wider word type to continue (at {})", word, index),
+            IntCompError::MissingParam { index, word, param } =>
+                write!(f, "{} has no parameter {} (at {})", word, param, index),
+            IntCompError::BadExtensionStatus { index, word } =>
+                write!(f, "{} can only return Ready, Outputed or Halted (at {})", word, index),
+        }
+    }
+}
+
+impl Error for IntCompError {}
+
+/// Why `process_stream` failed: the program itself, or reading its input or writing its output.
+#[derive(Debug)]
+pub enum StreamError {
+    Machine(IntCompError),
+    Io(io::Error),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Machine(error) => write!(f, "{}", error),
+            StreamError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StreamError::Machine(error) => Some(error),
+            StreamError::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<IntCompError> for StreamError {
+    fn from(error: IntCompError) -> Self {
+        StreamError::Machine(error)
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+
+use crate::{Program, error::IntCompError, instruction::{Instruction, Status, param::{Param, Params}}, word::Word};
+
+/// Runs one extension instruction; see `IntComp::register_opcode`.
+pub type Handler<W> = Box<dyn FnMut(&mut ExtensionContext<'_, W>) -> Result<Status<W>, IntCompError>>;
+
+/// What an extension handler can see and change while its instruction executes.
+pub struct ExtensionContext<'a, W = i64> {
+    program: &'a mut Program<W>,
+    params: Params<W>,
+    next: usize,
+    written: &'a mut Vec<(usize, W)>,
+}
+
+impl<'a, W: Word> ExtensionContext<'a, W> {
+    /// Address of the instruction being executed.
+    pub fn index(&self) -> usize {
+        self.program.index
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.program.relative_base
+    }
+
+    pub fn params(&self) -> &[Param<W>] {
+        &self.params
+    }
+
+    /// Reads parameter `param`, following its mode like the built-in instructions do.
+    pub fn read(&mut self, param: usize) -> Result<W, IntCompError> {
+        self.param(param)?.get_value(self.program)
+    }
+
+    /// Writes parameter `param`; immediate parameters fail with `WriteToImmediate`.
+    pub fn write(&mut self, param: usize, value: W) -> Result<(), IntCompError> {
+        let param = self.param(param)?;
+        let old = param.resolve(self.program).map(|address| (address, self.program.get(address)));
+        param.set_value(self.program, value)?;
+        self.written.extend(old);
+
+        Ok(())
+    }
+
+    pub fn peek(&self, address: usize) -> W {
+        self.program.memory.get(address)
+    }
+
+    pub fn poke(&mut self, address: usize, value: W) {
+        self.written.push((address, self.program.get(address)));
+        self.program.last_write = Some((address, value.clone()));
+        self.program.set(address, value);
+    }
+
+    /// Continues at `target` instead of the next instruction.
+    pub fn jump(&mut self, target: usize) {
+        self.next = target;
+    }
+
+    fn param(&self, param: usize) -> Result<Param<W>, IntCompError> {
+        self.params.get(param).cloned()
+            .ok_or(IntCompError::MissingParam { index: self.program.index, word: self.program.current_word(), param })
+    }
+}
+
+struct Extension<W> {
+    param_count: u8,
+    handler: Handler<W>,
+}
+
+/// Opcodes added on top of the built-in instruction set.
+pub(crate) struct Extensions<W> {
+    opcodes: BTreeMap<u8, Extension<W>>,
+}
+
+impl<W> Default for Extensions<W> {
+    fn default() -> Self {
+        Extensions { opcodes: BTreeMap::new() }
+    }
+}
+
+impl<W: Word> Extensions<W> {
+    pub fn register(&mut self, opcode: u8, param_count: u8, handler: Handler<W>) {
+        assert!(opcode < 100 && Instruction::new(0, &(opcode as i64)).is_err(), "opcode {} is built in or too large", opcode);
+        assert!(param_count <= 3, "extensions take at most 3 parameters, not {}", param_count);
+
+        self.opcodes.insert(opcode, Extension { param_count, handler });
+    }
+
+    /// Decodes a word the built-in instruction set rejected with `error`.
+    pub fn decode(&self, word: i64, error: IntCompError) -> Result<Instruction, IntCompError> {
+        match self.opcodes.get(&((word % 100) as u8)) {
+            Some(extension) if word >= 0 => Instruction::extension(error.index(), word, extension.param_count),
+            _ => Err(error),
+        }
+    }
+
+    /// Runs the extension instruction at the program's index, whose parameters start at `index`.
+    /// Returns its status and the address to continue at, and appends the old values of the cells
+    /// it wrote to `written`. If the handler fails, its writes are put back so the program stays
+    /// at the failing instruction, like with the built-in instructions.
+    pub fn execute(&mut self, program: &mut Program<W>, opcode: u8, params: Params<W>, index: usize,
+        written: &mut Vec<(usize, W)>) -> Result<(Status<W>, usize), IntCompError> {
+        let extension = self.opcodes.get_mut(&opcode)
+            .ok_or(IntCompError::UnknownOpcode { index: program.index, word: program.current_word() })?;
+        let next = index + params.len();
+        let start = written.len();
+        let mut context = ExtensionContext { program, params, next, written };
+        let result = match (extension.handler)(&mut context) {
+            Ok(Status::RequestedInput | Status::BudgetExhausted) => Err(IntCompError::BadExtensionStatus {
+                index: context.program.index,
+                word: context.program.current_word(),
+            }),
+            result => result,
+        };
+
+        match result {
+            Ok(status) => Ok((status, context.next)),
+            Err(error) => {
+                for (address, value) in context.written.drain(start..).rev() {
+                    context.program.set(address, value);
+                }
+                context.program.last_write = None;
+                Err(error)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{IntComp, error::IntCompError, instruction::Status};
+
+    // Divides and takes the remainder of 17 by 5, outputting both.
+    const DIVMOD: [i64; 15] = [1110, 17, 5, 13, 1111, 17, 5, 14, 4, 13, 4, 14, 99, 0, 0];
+
+    fn with_divmod(program: &[i64]) -> IntComp {
+        let mut int_comp = IntComp::new(program);
+        for opcode in [10, 11] {
+            int_comp.register_opcode(opcode, 3, move |context| {
+                let dividend = context.read(0)?;
+                let divisor = context.read(1)?;
+                context.write(2, if opcode == 10 { dividend / divisor } else { dividend % divisor })?;
+                Ok(Status::Ready)
+            });
+        }
+
+        int_comp
+    }
+
+    #[test]
+    fn extensions_run_registered_opcodes() {
+        let mut int_comp = with_divmod(&DIVMOD);
+
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(3));
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(2));
+        assert_eq!(int_comp.run().unwrap(), Status::Halted);
+    }
+
+    #[test]
+    fn extensions_can_jump_and_output() {
+        let program = vec![1012, 7, 6, 99, 104, 1, 99, 42];
+        let mut int_comp = IntComp::new(&program);
+        int_comp.register_opcode(12, 2, |context| {
+            let target = context.read(1)?;
+            let value = context.read(0)?;
+            context.jump(target as usize);
+            Ok(Status::Outputed(value))
+        });
+
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(42));
+        assert_eq!(int_comp.index(), 6);
+        assert_eq!(int_comp.run().unwrap(), Status::Halted);
+    }
+
+    #[test]
+    fn extensions_leave_unregistered_opcodes_unknown() {
+        let mut int_comp = with_divmod(&[42]);
+
+        assert_eq!(int_comp.run().unwrap_err(), IntCompError::UnknownOpcode { index: 0, word: 42 });
+    }
+
+    #[test]
+    fn extension_writes_can_be_undone() {
+        let program = vec![13, 5, 99, 0, 0, 7];
+        let mut int_comp = IntComp::new(&program);
+        int_comp.set_history_limit(Some(4));
+        int_comp.register_opcode(13, 1, |context| {
+            let value = context.read(0)?;
+            context.poke(3, value);
+            context.poke(4, value * 2);
+            Ok(Status::Ready)
+        });
+
+        int_comp.run().unwrap();
+        assert_eq!(int_comp.peek_range(3, 2), vec![7, 14]);
+        assert_eq!(int_comp.last_writer(4), Some(0));
+
+        while int_comp.step_back() {}
+        assert_eq!(int_comp.get_program(), program);
+    }
+
+    #[test]
+    fn extensions_reject_missing_params() {
+        let mut int_comp = IntComp::new(&[50, 99]);
+        int_comp.register_opcode(50, 0, |context| {
+            context.poke(1, 7);
+            context.read(0)?;
+            Ok(Status::Ready)
+        });
+
+        assert_eq!(int_comp.run().unwrap_err(), IntCompError::MissingParam { index: 0, word: 50, param: 0 });
+        assert_eq!(int_comp.get_program(), vec![50, 99]);
+        assert_eq!(int_comp.index(), 0);
+    }
+
+    #[test]
+    fn extensions_cannot_request_input() {
+        let mut int_comp = IntComp::new(&[51, 2, 99]);
+        int_comp.register_opcode(51, 1, |context| {
+            context.write(0, 1)?;
+            Ok(Status::RequestedInput)
+        });
+
+        assert_eq!(int_comp.run().unwrap_err(), IntCompError::BadExtensionStatus { index: 0, word: 51 });
+        assert_eq!(int_comp.get_program(), vec![51, 2, 99]);
+        assert_eq!(int_comp.index(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "opcode 1 is built in")]
+    fn extensions_cannot_replace_built_in_opcodes() {
+        IntComp::new(&[99]).register_opcode(1, 3, |_| Ok(Status::Ready));
+    }
+}
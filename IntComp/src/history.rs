@@ -0,0 +1,66 @@
+use std::collections::VecDeque;
+
+use crate::instruction::Status;
+
+/// Machine state from before one executed instruction.
+#[derive(Clone)]
+#[derive(Debug)]
+pub(crate) struct Undo<W> {
+    pub index: usize,
+    pub relative_base: i64,
+    pub status: Status<W>,
+    pub executed: u64,
+    /// The cells the instruction wrote and their values before the writes, in write order.
+    pub writes: Vec<(usize, W)>,
+    /// The input value the instruction consumed.
+    pub input: Option<W>,
+    /// Length of the dense memory, which a write past the image grows.
+    pub dense_len: usize,
+}
+
+/// The most recent `limit` executed instructions, for stepping backwards.
+#[derive(Clone)]
+#[derive(Debug)]
+pub(crate) struct History<W> {
+    undos: VecDeque<Undo<W>>,
+    limit: usize,
+}
+
+impl<W> History<W> {
+    pub fn new(limit: usize) -> Self {
+        History { undos: VecDeque::new(), limit }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn len(&self) -> usize {
+        self.undos.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.undos.clear();
+    }
+
+    pub fn push(&mut self, undo: Undo<W>) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.undos.len() == self.limit {
+            self.undos.pop_front();
+        }
+        self.undos.push_back(undo);
+    }
+
+    pub fn pop(&mut self) -> Option<Undo<W>> {
+        self.undos.pop_back()
+    }
+
+    /// Instruction pointer of the most recent instruction that wrote `address`.
+    pub fn last_writer(&self, address: usize) -> Option<usize> {
+        self.undos.iter().rev()
+            .find(|undo| undo.writes.iter().any(|(written, _)| *written == address))
+            .map(|undo| undo.index)
+    }
+}
@@ -1,16 +1,21 @@
+use crate::error::IntCompError;
+
 use self::param::{ParamMode, Opcode};
 
 pub mod param;
 
 #[derive(PartialEq, Clone, Copy)]
 #[derive(Debug)]
-pub enum Status {
+pub enum Status<W = i64> {
     Ready,
     RequestedInput,
-    Outputed(i64),
-    Halted
+    Outputed(W),
+    Halted,
+    /// Returned by `run_for` and `run_until` when the program is still running.
+    BudgetExhausted
 }
 
+#[derive(Clone, Copy)]
 #[derive(Debug)]
 pub enum Instruction {
     Add(Opcode),
@@ -22,14 +27,21 @@ pub enum Instruction {
     LessThan(Opcode),
     Equals(Opcode),
     AdjustRelativeBase(Opcode),
-    Halt
+    Halt,
+    /// An opcode registered with `IntComp::register_opcode`.
+    Extension(u8, Opcode),
 }
 
 impl Instruction {
-    pub fn new(opcode: &i64) -> Self{
-        let opcode = Instruction::parse_opcode(&opcode.to_string());
+    pub fn new(index: usize, opcode: &i64) -> Result<Self, IntCompError> {
+        let word = *opcode;
+        if word < 0 {
+            return Err(IntCompError::UnknownOpcode { index, word });
+        }
+        let opcode = Instruction::parse_opcode(word)
+            .map_err(|mode| IntCompError::BadParamMode { index, word, mode })?;
 
-        match opcode.0 {
+        let instruction = match opcode.0 {
             1 => Instruction::Add(Opcode {param_count: 3, param_config: opcode.1 }),
             2 => Instruction::Multiply(Opcode {param_count: 3, param_config: opcode.1 }),
             3 => Instruction::Input(Opcode {param_count: 1, param_config: opcode.1 }),
@@ -40,31 +52,83 @@ impl Instruction {
             8 => Instruction::Equals(Opcode {param_count: 3, param_config: opcode.1}),
             9 => Instruction::AdjustRelativeBase(Opcode {param_count: 1, param_config: opcode.1}),
             99 => Instruction::Halt,
-            number => panic!("{} is not supported opcode", number),
+            _ => return Err(IntCompError::UnknownOpcode { index, word }),
+        };
+
+        Ok(instruction)
+    }
+
+    /// Decodes `word` as an extension opcode taking `param_count` parameters.
+    pub(crate) fn extension(index: usize, word: i64, param_count: u8) -> Result<Self, IntCompError> {
+        let (code, param_config) = Instruction::parse_opcode(word)
+            .map_err(|mode| IntCompError::BadParamMode { index, word, mode })?;
+
+        Ok(Instruction::Extension(code, Opcode { param_count, param_config }))
+    }
 
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Add(_) => "ADD",
+            Instruction::Multiply(_) => "MUL",
+            Instruction::Input(_) => "IN",
+            Instruction::Output(_) => "OUT",
+            Instruction::JumpTrue(_) => "JT",
+            Instruction::JumpFalse(_) => "JF",
+            Instruction::LessThan(_) => "LT",
+            Instruction::Equals(_) => "EQ",
+            Instruction::AdjustRelativeBase(_) => "ARB",
+            Instruction::Halt => "HLT",
+            Instruction::Extension(..) => "EXT",
         }
     }
 
-    fn parse_opcode(opcode: &str) -> (u8, Vec<ParamMode>){
-        let len = opcode.len();
-        let mut oc = 0;
-        let mut pc = Vec::new();
-        if len < 2 {
-            oc = opcode.parse().expect("Opcode must be int");
-        } else {
-            oc = opcode[len-2..].parse().expect("Opcode must be int");
-            for char in opcode[..len-2].chars().rev() {
-                let i: u8 = char.to_digit(10).unwrap() as u8;
-                pc.push(match i {
-                    0 => ParamMode::Position,
-                    1 => ParamMode::Immediate,
-                    2 => ParamMode::Relative,
-                    number => panic!("{} is invalid parameter mode", number)
-                });
+    pub fn opcode(&self) -> Option<&Opcode> {
+        match self {
+            Instruction::Add(oc)
+            | Instruction::Multiply(oc)
+            | Instruction::Input(oc)
+            | Instruction::Output(oc)
+            | Instruction::JumpTrue(oc)
+            | Instruction::JumpFalse(oc)
+            | Instruction::LessThan(oc)
+            | Instruction::Equals(oc)
+            | Instruction::AdjustRelativeBase(oc)
+            | Instruction::Extension(_, oc) => Some(oc),
+            Instruction::Halt => None,
+        }
+    }
+
+    /// Position of the parameter the instruction writes to, if it writes at all.
+    pub fn write_param(&self) -> Option<usize> {
+        match self {
+            Instruction::Add(_) | Instruction::Multiply(_) | Instruction::LessThan(_) | Instruction::Equals(_) => Some(2),
+            Instruction::Input(_) => Some(0),
+            _ => None,
+        }
+    }
+
+    /// Splits the opcode word into its opcode and parameter modes, or returns the invalid mode digit.
+    /// Mode digits past the third parameter must still be valid, but are otherwise ignored.
+    fn parse_opcode(opcode: i64) -> Result<(u8, [ParamMode; 3]), u8> {
+        let oc = (opcode % 100) as u8;
+        let mut modes = opcode / 100;
+        let mut pc = [ParamMode::Position; 3];
+        let mut i = 0;
+        while modes > 0 {
+            let mode = match modes % 10 {
+                0 => ParamMode::Position,
+                1 => ParamMode::Immediate,
+                2 => ParamMode::Relative,
+                number => return Err(number as u8)
+            };
+            if i < pc.len() {
+                pc[i] = mode;
             }
+            modes /= 10;
+            i += 1;
         }
 
-        (oc, pc)
+        Ok((oc, pc))
     }
 }
 
@@ -74,31 +138,60 @@ mod tests {
 
     #[test]
     fn parse_opcode_parses_2() {
-        let oc = Instruction::parse_opcode("2");
+        let oc = Instruction::parse_opcode(2).unwrap();
 
         assert_eq!(oc.0, 2);
     }
 
     #[test]
-    fn parse_opcode_parses_02() {
-        let oc = Instruction::parse_opcode("02");
+    fn parse_opcode_parses_1002() {
+        let oc = Instruction::parse_opcode(1002).unwrap();
 
         assert_eq!(oc.0, 2);
+        assert_eq!(oc.1, [ParamMode::Position, ParamMode::Immediate, ParamMode::Position]);
     }
 
     #[test]
     fn parse_opcode_parses_1199() {
-        let oc = Instruction::parse_opcode("1199");
+        let oc = Instruction::parse_opcode(1199).unwrap();
 
         assert_eq!(oc.0, 99);
-        assert_eq!(oc.1, vec![ParamMode::Immediate; 2]);
+        assert_eq!(oc.1, [ParamMode::Immediate, ParamMode::Immediate, ParamMode::Position]);
     }
 
     #[test]
     fn parse_opcode_parses_10103() {
-        let oc = Instruction::parse_opcode("10103");
+        let oc = Instruction::parse_opcode(10103).unwrap();
 
         assert_eq!(oc.0, 3);
-        assert_eq!(oc.1, vec![ParamMode::Immediate, ParamMode::Position, ParamMode::Immediate]);
+        assert_eq!(oc.1, [ParamMode::Immediate, ParamMode::Position, ParamMode::Immediate]);
+    
+    }
+
+    #[test]
+    fn parse_opcode_rejects_mode_3() {
+        let oc = Instruction::parse_opcode(301);
+
+        assert_eq!(oc.unwrap_err(), 3);
+    }
+
+    #[test]
+    fn parse_opcode_checks_extra_mode_digits() {
+        assert!(Instruction::parse_opcode(1_111_101).is_ok());
+        assert_eq!(Instruction::parse_opcode(3_111_101).unwrap_err(), 3);
+    }
+
+    #[test]
+    fn new_rejects_unknown_opcode() {
+        let inst = Instruction::new(4, &42);
+
+        assert_eq!(inst.unwrap_err(), IntCompError::UnknownOpcode { index: 4, word: 42 });
+    }
+
+    #[test]
+    fn new_rejects_bad_param_mode() {
+        let inst = Instruction::new(0, &1301);
+
+        assert_eq!(inst.unwrap_err(), IntCompError::BadParamMode { index: 0, word: 1301, mode: 3 });
     }
 }
\ No newline at end of file
@@ -1,64 +1,123 @@
-use crate::Program;
+use std::{fmt, ops::Deref};
 
-#[derive(Clone)]
+use crate::{Program, error::IntCompError, word::Word};
+
+#[derive(Clone, Copy)]
 #[derive(Debug)]
 pub struct Opcode {
     pub param_count: u8,
-    pub param_config: Vec<ParamMode>
+    pub param_config: [ParamMode; 3]
 }
 
 #[derive(Clone, Copy)]
 #[derive(Debug)]
 #[derive(PartialEq, Eq)]
+#[derive(Default)]
 pub enum ParamMode {
+    #[default]
     Position,
     Immediate,
     Relative
 }
 
-impl ParamMode {
-    fn default() -> Self{
-        ParamMode::Position
-    }
-}
-
+#[derive(Clone, Copy, Default)]
 #[derive(Debug)]
-pub struct Param {
+pub struct Param<W = i64> {
     pub index: usize,
-    pub value: i64, 
+    pub value: W, 
     pub config: ParamMode
 }
 
-impl Param {
-    pub fn get_value(&self, program: &mut Program) -> i64 {
-        let position = self.value as usize;
-        let relative = (program.relative_base as i64 + self.value) as usize;
+/// The parameters of one instruction, kept inline so decoding does not allocate.
+#[derive(Clone, Copy, Default)]
+#[derive(Debug)]
+pub struct Params<W = i64> {
+    params: [Param<W>; 3],
+    len: usize,
+}
+
+impl<W> Deref for Params<W> {
+    type Target = [Param<W>];
+
+    fn deref(&self) -> &[Param<W>] {
+        &self.params[..self.len]
+    }
+}
+
+impl<W: Word> Param<W> {
+    pub fn get_value(&self, program: &mut Program<W>) -> Result<W, IntCompError> {
         let value = match self.config {
-            ParamMode::Position => program.get(position),
-            ParamMode::Relative => program.get(relative),
-            ParamMode::Immediate => self.value,
+            ParamMode::Position | ParamMode::Relative => program.get(self.address(program)?),
+            ParamMode::Immediate => self.value.clone(),
         };
 
-        value
+        Ok(value)
     }
-    pub fn set_value(&self, program: &mut Program, value: i64) {
-        let position = self.value as usize;
-        let relative = (program.relative_base as i64 + self.value) as usize;
+
+    pub fn set_value(&self, program: &mut Program<W>, value: W) -> Result<(), IntCompError> {
+        let address = self.address(program)?;
+        program.last_write = Some((address, value.clone()));
+        program.set(address, value);
+
+        Ok(())
+    }
+
+    /// Resolves the value without failing, reading 0 for addresses that cannot be accessed.
+    pub fn peek_value(&self, program: &Program<W>) -> W {
         match self.config {
-            ParamMode::Position => program.set(position, value),
-            ParamMode::Relative => program.set(relative, value),
-            ParamMode::Immediate => program.set(self.index, value)
+            ParamMode::Immediate => self.value.clone(),
+            _ => self.address(program)
+                .map(|address| program.memory.get(address))
+                .unwrap_or_default(),
         }
     }
 
-    pub fn get_params(program: &Program, index: &usize, oc: &Opcode) -> Vec<Param> {
-        let mut params: Vec<Param> = Vec::new();
-        for i in 0..oc.param_count {
-            let i = i as usize;
-            let value = *program.memory.get(index + i).expect("instruction missing");
-            let config = oc.param_config.get(i).unwrap_or(&ParamMode::default()).clone();
-            params.push(Param {index: index + i, value, config })
+    /// The address the parameter refers to, or `None` for immediate and inaccessible parameters.
+    pub(crate) fn resolve(&self, program: &Program<W>) -> Option<usize> {
+        self.address(program).ok()
+    }
+
+    fn address(&self, program: &Program<W>) -> Result<usize, IntCompError> {
+        match self.config {
+            ParamMode::Position => program.word_address(&self.value),
+            ParamMode::Relative => {
+                let address = self.value.to_i64()
+                    .and_then(|offset| program.relative_base.checked_add(offset))
+                    .ok_or(IntCompError::Overflow { index: program.index, word: program.current_word() })?;
+                program.check_address(address)
+            },
+            ParamMode::Immediate => Err(IntCompError::WriteToImmediate {
+                index: program.index, word: program.current_word() }),
+        }
+    }
+
+    pub fn get_params(program: &Program<W>, index: &usize, oc: &Opcode) -> Result<Params<W>, IntCompError> {
+        Param::read(|address| program.memory.fetch(address), *index, oc)
+            .ok_or(IntCompError::IndexOutOfRange { index: program.index })
+    }
+
+    /// Reads the parameters starting at `index`, or `None` if the memory ends before all of them.
+    pub fn decode(memory: &[W], index: usize, oc: &Opcode) -> Option<Params<W>> {
+        Param::read(|address| memory.get(address), index, oc)
+    }
+
+    fn read<'a>(fetch: impl Fn(usize) -> Option<&'a W>, index: usize, oc: &Opcode) -> Option<Params<W>> {
+        let mut params = Params { len: oc.param_count as usize, ..Params::default() };
+        for i in 0..params.len {
+            let value = fetch(index + i)?.clone();
+            params.params[i] = Param {index: index + i, value, config: oc.param_config[i] };
         };
-        params
+        Some(params)
+    }
+}
+
+impl<W: Word> fmt::Display for Param<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.config {
+            ParamMode::Position => write!(f, "[{}]", self.value),
+            ParamMode::Immediate => write!(f, "#{}", self.value),
+            ParamMode::Relative if self.value < W::default() => write!(f, "rb{}", self.value),
+            ParamMode::Relative => write!(f, "rb+{}", self.value),
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write, StdinLock, Stdout};
+
+pub trait IntcodeIo<W = i64> {
+    /// Returns the next input value, or `None` when no input is available yet.
+    fn read(&mut self) -> Option<W>;
+    fn write(&mut self, value: W);
+}
+
+#[derive(Debug, Default)]
+pub struct QueueIo<W = i64> {
+    pub input: VecDeque<W>,
+    pub output: VecDeque<W>,
+}
+
+impl QueueIo {
+    pub fn new(input: &[i64]) -> Self {
+        QueueIo { input: input.iter().copied().collect(), output: VecDeque::new() }
+    }
+}
+
+impl<W> IntcodeIo<W> for QueueIo<W> {
+    fn read(&mut self) -> Option<W> {
+        self.input.pop_front()
+    }
+
+    fn write(&mut self, value: W) {
+        self.output.push_back(value);
+    }
+}
+
+/// Reads one number per line and writes one per line. The first line that is not a number, or the
+/// first I/O error, stops the stream: it reads no more input and drops further output, and the
+/// error is kept for `error`.
+pub struct StreamIo<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    line: usize,
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead, W: Write> StreamIo<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        StreamIo { input, output, line: 0, error: None }
+    }
+
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+impl StreamIo<StdinLock<'static>, Stdout> {
+    pub fn stdio() -> Self {
+        StreamIo::new(io::stdin().lock(), io::stdout())
+    }
+}
+
+impl<R: BufRead, W: Write> IntcodeIo for StreamIo<R, W> {
+    fn read(&mut self) -> Option<i64> {
+        while self.error.is_none() {
+            let mut buffer = String::new();
+            match self.input.read_line(&mut buffer) {
+                Ok(0) => return None,
+                Ok(_) => self.line += 1,
+                Err(error) => self.error = Some(error),
+            }
+
+            let text = buffer.trim();
+            if text.is_empty() {
+                continue;
+            }
+            match text.parse() {
+                Ok(value) => return Some(value),
+                Err(_) => self.error = Some(io::Error::new(io::ErrorKind::InvalidData,
+                    format!("line {}: {} is not a number", self.line, text))),
+            }
+        }
+
+        None
+    }
+
+    fn write(&mut self, value: i64) {
+        if self.error.is_none() {
+            if let Err(error) = writeln!(self.output, "{}", value) {
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+pub struct FnIo<F, G> {
+    input: F,
+    output: G,
+}
+
+impl<F, G> FnIo<F, G> {
+    pub fn new(input: F, output: G) -> Self {
+        FnIo { input, output }
+    }
+}
+
+impl<W, F, G> IntcodeIo<W> for FnIo<F, G>
+where F: FnMut() -> Option<W>,
+    G: FnMut(W)
+{
+    fn read(&mut self) -> Option<W> {
+        (self.input)()
+    }
+
+    fn write(&mut self, value: W) {
+        (self.output)(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_io_reads_in_order() {
+        let mut io = QueueIo::new(&[1, 2]);
+
+        assert_eq!(io.read(), Some(1));
+        assert_eq!(io.read(), Some(2));
+        assert_eq!(io.read(), None);
+    }
+
+    #[test]
+    fn stream_io_parses_lines() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut io = StreamIo::new(" 13\n-4\n".as_bytes(), &mut output);
+
+        assert_eq!(io.read(), Some(13));
+        assert_eq!(io.read(), Some(-4));
+        assert_eq!(io.read(), None);
+
+        io.write(5);
+        assert_eq!(output, b"5\n");
+    }
+
+    #[test]
+    fn stream_io_stops_at_bad_line() {
+        let mut io = StreamIo::new("1\n\n1o\n2\n".as_bytes(), Vec::new());
+
+        assert_eq!(io.read(), Some(1));
+        assert_eq!(io.read(), None);
+        assert_eq!(io.read(), None);
+        assert_eq!(io.error().unwrap().to_string(), "line 3: 1o is not a number");
+    }
+
+    #[test]
+    fn stream_io_keeps_write_error() {
+        let mut output = [0u8; 2];
+        let mut io = StreamIo::new("".as_bytes(), &mut output[..]);
+
+        io.write(7);
+        io.write(12345);
+        io.write(8);
+
+        assert_eq!(io.take_error().unwrap().kind(), io::ErrorKind::WriteZero);
+    }
+}
@@ -1,205 +1,587 @@
-use std::{env, path, fs};
+#![allow(non_snake_case)]
 
-use instruction::{Status, Instruction, param::{Param, Opcode}};
+use std::{env, path, collections::VecDeque, io::{BufRead, Write}, time::Instant};
 
+use error::{IntCompError, StreamError};
+use extension::{ExtensionContext, Extensions};
+use history::{History, Undo};
+use instruction::{Status, Instruction, param::{Param, Params}};
+use io::{IntcodeIo, StreamIo};
+use memory::Memory;
+use overflow::Overflow;
+use profile::Profile;
+use snapshot::Snapshot;
+use trace::{Tracer, TraceEvent};
+use transcript::{Transcript, TranscriptEvent};
+use word::Word;
+
+pub mod ascii;
+pub mod asm;
+pub mod cfg;
+pub mod channel;
+pub mod debugger;
+pub mod disasm;
+pub mod error;
+pub mod extension;
+mod history;
 pub mod instruction;
+pub mod io;
+pub mod lint;
+pub mod loader;
+pub mod memory;
+pub mod network;
+pub mod overflow;
+pub mod profile;
+pub mod snapshot;
+pub mod trace;
+pub mod transcript;
+pub mod word;
 
+#[derive(Clone)]
 #[derive(Debug)]
-pub struct Program {
-    pub memory: Vec<i64>,
+pub struct Program<W = i64> {
+    pub memory: Memory<W>,
     index: usize,
-    oc: Option<Opcode>,
-    relative_base: usize,
-    pub status: Status,
+    relative_base: i64,
+    input: VecDeque<W>,
+    last_write: Option<(usize, W)>,
+    max_address: Option<usize>,
+    executed: u64,
+    /// Instructions already decoded at each address of the dense image, `None` when caching is off.
+    decoded: Option<Vec<Option<Instruction>>>,
+    pub status: Status<W>,
 }
 
 impl Program {
     fn new(program: Vec<i64>) -> Self {
-        Program { memory: program, index: 0, oc: None, relative_base: 0, status: Status::Ready }
+        Program::from_words(program)
+    }
+}
+
+impl<W: Word> Program<W> {
+    fn from_words(program: Vec<W>) -> Self {
+        Program {
+            memory: Memory::from_words(program),
+            index: 0,
+            relative_base: 0,
+            input: VecDeque::new(),
+            last_write: None,
+            max_address: None,
+            executed: 0,
+            decoded: Some(Vec::new()),
+            status: Status::Ready
+        }
     }
 
-    fn extend_memory_to(&mut self, size: usize){
-        let length = self.memory.len();
-        let mut extension = vec![0i64;size - length + 1];
-        self.memory.append(&mut extension);
+    fn get(&self, index: usize) -> W {
+        self.memory.get(index)
     }
 
-    fn get(&mut self, index: usize) -> i64 {
-        if self.memory.len() <= index {
-            self.extend_memory_to(index);
+    fn set(&mut self, index: usize, value: W){
+        self.memory.set(index, value);
+        if let Some(cached) = self.decoded.as_mut().and_then(|decoded| decoded.get_mut(index)) {
+            *cached = None;
         }
+    }
 
-        *self.memory.get(index).unwrap()
+    fn truncate(&mut self, len: usize) {
+        self.memory.truncate(len);
+        if let Some(decoded) = self.decoded.as_mut() {
+            decoded.truncate(len);
+        }
     }
 
-    fn set(&mut self, index: usize, value: i64){
-        if self.memory.len() <= index {
-            self.extend_memory_to(index);
+    fn decode(&mut self, index: usize) -> Result<Instruction, IntCompError> {
+        if let Some(Some(inst)) = self.decoded.as_ref().and_then(|decoded| decoded.get(index)) {
+            return Ok(*inst);
         }
 
-        self.memory[index] = value;
+        let word = self.memory.fetch(index).ok_or(IntCompError::IndexOutOfRange { index })?;
+        let inst = Instruction::new(index, &word.clamp_i64())?;
+        let dense_len = self.memory.as_slice().len();
+        if let Some(decoded) = self.decoded.as_mut().filter(|_| index < dense_len) {
+            if decoded.len() <= index {
+                decoded.resize(dense_len, None);
+            }
+            decoded[index] = Some(inst);
+        }
+
+        Ok(inst)
+    }
+
+    fn promote<V: Word + From<W>>(&self) -> Program<V> {
+        Program {
+            memory: self.memory.promote(),
+            index: self.index,
+            relative_base: self.relative_base,
+            input: self.input.iter().cloned().map(V::from).collect(),
+            last_write: self.last_write.clone().map(|(address, value)| (address, V::from(value))),
+            max_address: self.max_address,
+            executed: self.executed,
+            decoded: self.decoded.clone(),
+            status: match self.status.clone() {
+                Status::Ready => Status::Ready,
+                Status::RequestedInput => Status::RequestedInput,
+                Status::Outputed(value) => Status::Outputed(V::from(value)),
+                Status::Halted => Status::Halted,
+                Status::BudgetExhausted => Status::BudgetExhausted,
+            },
+        }
+    }
+
+    fn current_word(&self) -> i64 {
+        self.memory.get(self.index).clamp_i64()
+    }
+
+    fn check_address(&self, address: i64) -> Result<usize, IntCompError> {
+        if address < 0 {
+            return Err(IntCompError::NegativeAddress { index: self.index, word: self.current_word(), address });
+        }
+        match self.max_address {
+            Some(max_address) if address as u64 > max_address as u64 =>
+                Err(IntCompError::AddressTooLarge { index: self.index, word: self.current_word(), address: address as u64 }),
+            _ => Ok(address as usize),
+        }
+    }
+
+    /// Like `check_address`, for a word that may not fit in an `i64`.
+    fn word_address(&self, address: &W) -> Result<usize, IntCompError> {
+        match address.to_i64() {
+            Some(address) => self.check_address(address),
+            None if *address < W::default() => self.check_address(i64::MIN),
+            None => Err(IntCompError::AddressTooLarge { index: self.index, word: self.current_word(), address: u64::MAX }),
+        }
     }
 }
 
-pub struct IntComp {
-    const_program: Vec<i64>,
-    program: Program,
+/// How many instructions `run_until` executes between looks at the clock.
+const DEADLINE_CHECK_STEPS: u64 = 1024;
+
+pub struct IntComp<W = i64> {
+    const_program: Vec<W>,
+    program: Program<W>,
+    tracer: Option<Box<dyn Tracer<W>>>,
+    profile: Option<Profile>,
+    transcript: Option<Transcript<W>>,
+    history: Option<History<W>>,
+    extensions: Extensions<W>,
+    overflow: Overflow,
 }
 
 impl IntComp {
-    pub fn new(program: &Vec<i64>) -> Self {
-        IntComp { const_program: program.clone(), program: Program::new(program.clone()) }
+    pub fn new(program: &[i64]) -> Self {
+        IntComp::from_words(program)
+    }
+}
+
+impl<W: Word> IntComp<W> {
+    /// Builds a machine over any word type, e.g. `IntComp::<i128>::from_words`.
+    pub fn from_words(program: &[W]) -> Self {
+        IntComp { const_program: program.to_vec(), program: Program::from_words(program.to_vec()), tracer: None, profile: None, transcript: None, history: None, extensions: Extensions::default(), overflow: Overflow::default() }
+    }
+
+    /// Calls `tracer` after every executed instruction.
+    pub fn set_tracer(&mut self, tracer: impl Tracer<W> + 'static) {
+        self.tracer = Some(Box::new(tracer));
+    }
+
+    pub fn take_tracer(&mut self) -> Option<Box<dyn Tracer<W>>> {
+        self.tracer.take()
+    }
+
+    /// Starts collecting a fresh `Profile` of everything executed from now on.
+    pub fn enable_profiling(&mut self) {
+        self.profile = Some(Profile::default());
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Stops profiling and returns what was collected.
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take()
+    }
+
+    /// Starts a fresh `Transcript` of every input consumed and output produced from now on.
+    /// Start it on a freshly loaded machine to replay it later.
+    pub fn start_recording(&mut self) {
+        self.transcript = Some(Transcript::default());
+    }
+
+    pub fn transcript(&self) -> Option<&Transcript<W>> {
+        self.transcript.as_ref()
     }
 
-    pub fn get_program(&self) -> Vec<i64> {
-        self.program.memory.clone()
+    /// Stops recording and returns the transcript so far.
+    pub fn take_transcript(&mut self) -> Option<Transcript<W>> {
+        let mut transcript = self.transcript.take()?;
+        transcript.executed = self.program.executed;
+        Some(transcript)
     }
 
-    pub fn reset(&mut self) -> Status {
+    /// Keeps an undo log of the last `limit` executed instructions so `step_back` can rewind
+    /// them, or drops it with `None`. Changes made with `poke` are not logged.
+    pub fn set_history_limit(&mut self, limit: Option<usize>) {
+        self.history = limit.map(History::new);
+    }
+
+    pub fn history_limit(&self) -> Option<usize> {
+        self.history.as_ref().map(|history| history.limit())
+    }
+
+    /// Number of instructions `step_back` can currently undo.
+    pub fn history_len(&self) -> usize {
+        self.history.as_ref().map_or(0, |history| history.len())
+    }
+
+    /// Undoes the last executed instruction: its memory write, consumed input, instruction
+    /// pointer and relative base. Outputs stay produced. Returns `false` when the history is empty.
+    pub fn step_back(&mut self) -> bool {
+        let Some(undo) = self.history.as_mut().and_then(|history| history.pop()) else {
+            return false;
+        };
+
+        for (address, old) in undo.writes.into_iter().rev() {
+            self.program.set(address, old);
+        }
+        self.program.truncate(undo.dense_len);
+        if let Some(value) = undo.input {
+            self.program.input.push_front(value);
+        }
+        self.program.index = undo.index;
+        self.program.relative_base = undo.relative_base;
+        self.program.status = undo.status;
+        self.program.executed = undo.executed;
+        self.program.last_write = None;
+
+        true
+    }
+
+    /// Instruction pointer of the most recent instruction in the history that wrote `address`.
+    pub fn last_writer(&self, address: usize) -> Option<usize> {
+        self.history.as_ref()?.last_writer(address)
+    }
+
+    /// Executes words with opcode `opcode` by calling `handler`, which gets the `param_count`
+    /// parameters decoded with the modes of the word and returns the status of the instruction.
+    /// Execution continues after the parameters unless the handler jumps. A handler can return
+    /// `Ready`, `Outputed` or `Halted`; other statuses and errors put back its writes and stop
+    /// the machine at the instruction. Built-in opcodes are decoded first, so extensions only
+    /// cost time on the words they handle.
+    ///
+    /// # Panics
+    ///
+    /// If `opcode` is built in or above 99, or `param_count` is above 3.
+    pub fn register_opcode(&mut self, opcode: u8, param_count: u8,
+        handler: impl FnMut(&mut ExtensionContext<'_, W>) -> Result<Status<W>, IntCompError> + 'static) {
+        self.extensions.register(opcode, param_count, Box::new(handler));
+    }
+
+    /// Returns the loaded image and the memory grown next to it; far-out cells are only visible through `peek`.
+    pub fn get_program(&self) -> Vec<W> {
+        self.program.memory.as_slice().to_vec()
+    }
+
+    /// Makes any access past `max_address` fail with `IntCompError::AddressTooLarge`.
+    pub fn set_max_address(&mut self, max_address: Option<usize>) {
+        self.program.max_address = max_address;
+    }
+
+    /// Turns the per-address cache of decoded instructions on or off. It is on by default; writes
+    /// to memory drop the cached entry, so self-modifying programs behave the same either way.
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.program.decoded = enabled.then(Vec::new);
+    }
+
+    /// Copies the machine onto the wider word type `V`, e.g. `int_comp.promote::<i128>()` once
+    /// `Overflow::Promote` stopped it; the copy resumes at the instruction that overflowed. The
+    /// overflow policy and decode cache carry over; tracer, profile, transcript, history and
+    /// extensions do not.
+    pub fn promote<V: Word + From<W>>(&self) -> IntComp<V> {
+        let const_program: Vec<V> = self.const_program.iter().cloned().map(V::from).collect();
+        let mut promoted = IntComp::from_words(&const_program);
+        promoted.program = self.program.promote();
+        promoted.overflow = self.overflow;
+
+        promoted
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    pub fn status(&self) -> Status<W> {
+        self.program.status.clone()
+    }
+
+    pub fn index(&self) -> usize {
+        self.program.index
+    }
+
+    /// Number of instructions executed since the machine was created or reset.
+    pub fn executed(&self) -> u64 {
+        self.program.executed
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.program.relative_base
+    }
+
+    pub fn peek(&self, address: usize) -> W {
+        self.program.memory.get(address)
+    }
+
+    /// Reads `len` cells from `address`, stopping early at the end of the address space.
+    pub fn peek_range(&self, address: usize, len: usize) -> Vec<W> {
+        (address..=usize::MAX).take(len).map(|address| self.peek(address)).collect()
+    }
+
+    pub fn poke(&mut self, address: usize, value: W) {
+        self.program.set(address, value);
+    }
+
+    pub fn reset(&mut self) -> Status<W> {
         let original_program = self.const_program.clone();
-        self.program =  Program::new(original_program);
+        let max_address = self.program.max_address;
+        let cache_enabled = self.program.decoded.is_some();
+        self.program =  Program::from_words(original_program);
+        self.program.max_address = max_address;
+        self.program.decoded = cache_enabled.then(Vec::new);
+        if let Some(history) = self.history.as_mut() {
+            history.clear();
+        }
 
         Status::Ready
     }
 
-    pub fn run(&mut self) -> Status {
-        if self.program.status == Status::Halted || self.program.status == Status::RequestedInput {
-            return self.program.status;
+    pub fn snapshot(&self) -> Snapshot<W> {
+        Snapshot { program: self.program.clone() }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot<W>) {
+        self.program = snapshot.program.clone();
+        if let Some(history) = self.history.as_mut() {
+            history.clear();
         }
+    }
 
+    pub fn push_input(&mut self, input: W) {
+        self.program.input.push_back(input);
+    }
+
+    pub fn extend_input(&mut self, inputs: impl IntoIterator<Item = W>) {
+        self.program.input.extend(inputs);
+    }
+
+    pub fn run(&mut self) -> Result<Status<W>, IntCompError> {
         'run_loop: loop {
-            let status = self.process_instruction();
+            let status = self.step()?;
 
             match status {
-                Status::Halted => break 'run_loop Status::Halted,
+                Status::Halted => break 'run_loop Ok(Status::Halted),
                 Status::Ready => continue,
-                Status::Outputed(value) => break 'run_loop Status::Outputed(value),
-                Status::RequestedInput => break 'run_loop Status::RequestedInput
+                Status::Outputed(value) => break 'run_loop Ok(Status::Outputed(value)),
+                Status::RequestedInput => break 'run_loop Ok(Status::RequestedInput),
+                Status::BudgetExhausted => break 'run_loop Ok(Status::BudgetExhausted)
             }
-    }
+        }
     }
 
-    pub fn run_with_input(&mut self, input: i64) -> Status{
-        if self.program.status != Status::RequestedInput {
-            return self.program.status;
+    /// Like `run`, but stops with `Status::BudgetExhausted` after executing `max_steps` instructions.
+    /// A machine that has halted or waits for input reports that even with a budget of 0.
+    pub fn run_for(&mut self, max_steps: u64) -> Result<Status<W>, IntCompError> {
+        if let Some(status) = self.stopped() {
+            return Ok(status);
+        }
+        for _ in 0..max_steps {
+            match self.step()? {
+                Status::Ready => continue,
+                status => return Ok(status),
+            }
         }
 
-        let oc = &self.program.oc.clone().unwrap();
-        let index = self.program.index;
-        let mut program = &mut self.program;
+        Ok(Status::BudgetExhausted)
+    }
 
-        let params = Param::get_params(program, &index, &oc);
-    
-        params[0].set_value(&mut program, input);
-        println!("Index before: {}", index);
-        let index = index + (oc.param_count as usize);
-        println!("Index before: {}", index);
-        self.program.status = Status::Ready;
-        self.program.index = index;
+    /// Like `run`, but stops with `Status::BudgetExhausted` once `deadline` has passed. The clock
+    /// is only checked every `DEADLINE_CHECK_STEPS` instructions, so the deadline may be overrun slightly.
+    pub fn run_until(&mut self, deadline: Instant) -> Result<Status<W>, IntCompError> {
+        if let Some(status) = self.stopped() {
+            return Ok(status);
+        }
+        loop {
+            if Instant::now() >= deadline {
+                return Ok(Status::BudgetExhausted);
+            }
+            match self.run_for(DEADLINE_CHECK_STEPS)? {
+                Status::BudgetExhausted => continue,
+                status => return Ok(status),
+            }
+        }
+    }
+
+    pub fn run_with_input(&mut self, input: W) -> Result<Status<W>, IntCompError> {
+        self.push_input(input);
         self.run()
     }
 
-    fn process_instruction(&mut self) -> Status {
+    /// Drives the program until it halts, or until `io` has no input left to supply.
+    pub fn run_with_io(&mut self, io: &mut impl IntcodeIo<W>) -> Result<Status<W>, IntCompError> {
+        loop {
+            match self.run()? {
+                Status::Outputed(value) => io.write(value),
+                Status::RequestedInput => match io.read() {
+                    Some(value) => self.push_input(value),
+                    None => break Ok(Status::RequestedInput),
+                },
+                status => break Ok(status),
+            }
+        }
+    }
+
+    /// Executes a single instruction, unless the program has halted or still waits for input.
+    pub fn step(&mut self) -> Result<Status<W>, IntCompError> {
+        match self.stopped() {
+            Some(status) => Ok(status),
+            None => self.process_instruction(),
+        }
+    }
+
+    /// The status of a program that cannot execute anything until it is reset or given input.
+    fn stopped(&self) -> Option<Status<W>> {
+        match self.program.status {
+            Status::Halted => Some(Status::Halted),
+            Status::RequestedInput if self.program.input.is_empty() => Some(Status::RequestedInput),
+            _ => None,
+        }
+    }
+
+    fn process_instruction(&mut self) -> Result<Status<W>, IntCompError> {
         let mut index = self.program.index;
-        let mut opcode = None;
-        let inst = Instruction::new(self.program.memory.get(index).expect("instruction missing"));
+        let inst = match self.program.decode(index) {
+            Err(error @ IntCompError::UnknownOpcode { word, .. }) => self.extensions.decode(word, error)?,
+            inst => inst?,
+        };
         index += 1;
 
-        let status = match inst {
+        let traced = self.tracer.as_ref().map(|_| self.peek_operands(&inst, index));
+        let reads = self.profile.as_ref().map(|_| self.read_addresses(&inst, index));
+        let mut undo = self.history.as_ref().map(|_| self.undo_entry(&inst, index));
+        self.program.last_write = None;
+
+        let status = match &inst {
             Instruction::Add(oc) => {
-                let params = Param::get_params(&self.program, &index, &oc);
+                let params = Param::get_params(&self.program, &index, oc)?;
     
-                let val1 = params[0].get_value(&mut self.program);
-                let val2 = params[1].get_value(&mut self.program);
-                params[2].set_value(&mut self.program, val1 + val2);
+                let val1 = params[0].get_value(&mut self.program)?;
+                let val2 = params[1].get_value(&mut self.program)?;
+                let result = self.arithmetic(self.overflow.add(&val1, &val2))?;
+                params[2].set_value(&mut self.program, result)?;
     
                 index += oc.param_count as usize;
                 Status::Ready
             },
             Instruction::Multiply(oc) => {
-                let params = Param::get_params(&self.program, &index, &oc);
+                let params = Param::get_params(&self.program, &index, oc)?;
     
-                let val1 = params[0].get_value(&mut self.program);
-                let val2 = params[1].get_value(&mut self.program);
-                params[2].set_value(&mut self.program, val1 * val2);
+                let val1 = params[0].get_value(&mut self.program)?;
+                let val2 = params[1].get_value(&mut self.program)?;
+                let result = self.arithmetic(self.overflow.mul(&val1, &val2))?;
+                params[2].set_value(&mut self.program, result)?;
     
                 index += oc.param_count as usize;
                 Status::Ready
             },
             Instruction::Input(oc) => {
-                opcode = Some(oc);
-                Status::RequestedInput
+                match self.program.input.front() {
+                    Some(value) => {
+                        let value = value.clone();
+                        let params = Param::get_params(&self.program, &index, oc)?;
+
+                        params[0].set_value(&mut self.program, value)?;
+                        self.program.input.pop_front();
+
+                        index += oc.param_count as usize;
+                        Status::Ready
+                    },
+                    None => {
+                        index = self.program.index;
+                        Status::RequestedInput
+                    }
+                }
             },
             Instruction::Output(oc) => {
-                let params = Param::get_params(&self.program, &index, &oc);
+                let params = Param::get_params(&self.program, &index, oc)?;
     
-                let value = params[0].get_value(&mut self.program);
+                let value = params[0].get_value(&mut self.program)?;
                 
                 index += oc.param_count as usize;
                 Status::Outputed(value)
             },
             Instruction::JumpTrue(oc) => {
-                let params = Param::get_params(&self.program, &index, &oc);
+                let params = Param::get_params(&self.program, &index, oc)?;
     
-                let val1 = params[0].get_value(&mut self.program);
-                let val2 = params[1].get_value(&mut self.program);
+                let val1 = params[0].get_value(&mut self.program)?;
+                let val2 = params[1].get_value(&mut self.program)?;
     
-                if val1 != 0 {
-                    index = val2 as usize;
+                if !val1.is_zero() {
+                    index = self.jump_target(&val2)?;
                 } else {
                     index += oc.param_count as usize;
                 }
                 Status::Ready
             },
             Instruction::JumpFalse(oc) => {
-                let params = Param::get_params(&self.program, &index, &oc);
+                let params = Param::get_params(&self.program, &index, oc)?;
     
-                let val1 = params[0].get_value(&mut self.program);
-                let val2 = params[1].get_value(&mut self.program);
+                let val1 = params[0].get_value(&mut self.program)?;
+                let val2 = params[1].get_value(&mut self.program)?;
     
-                if val1 == 0 {
-                    index = val2 as usize;
+                if val1.is_zero() {
+                    index = self.jump_target(&val2)?;
                 } else {
                     index += oc.param_count as usize;
                 }
                 Status::Ready
             },
             Instruction::LessThan(oc) => {
-                let params = Param::get_params(&self.program, &index, &oc);
+                let params = Param::get_params(&self.program, &index, oc)?;
     
-                let val1 = params[0].get_value(&mut self.program);
-                let val2 = params[1].get_value(&mut self.program);
+                let val1 = params[0].get_value(&mut self.program)?;
+                let val2 = params[1].get_value(&mut self.program)?;
     
                 if val1 < val2 {
-                    params[2].set_value(&mut self.program, 1);
+                    params[2].set_value(&mut self.program, W::from_i64(1))?;
                 } else {
-                    params[2].set_value(&mut self.program, 0);
+                    params[2].set_value(&mut self.program, W::from_i64(0))?;
                 }
     
                 index += oc.param_count as usize;
                 Status::Ready
             },
             Instruction::Equals(oc) => {
-                let params = Param::get_params(&self.program, &index, &oc);
+                let params = Param::get_params(&self.program, &index, oc)?;
     
-                let val1 = params[0].get_value(&mut self.program);
-                let val2 = params[1].get_value(&mut self.program);
+                let val1 = params[0].get_value(&mut self.program)?;
+                let val2 = params[1].get_value(&mut self.program)?;
     
                 if val1 == val2 {
-                    params[2].set_value(&mut self.program, 1);
+                    params[2].set_value(&mut self.program, W::from_i64(1))?;
                 } else {
-                    params[2].set_value(&mut self.program, 0);
+                    params[2].set_value(&mut self.program, W::from_i64(0))?;
                 }
                 
                 index += oc.param_count as usize;
                 Status::Ready
             },
             Instruction::AdjustRelativeBase(oc) => {
-                let params = Param::get_params(&self.program, &index, &oc);
+                let params = Param::get_params(&self.program, &index, oc)?;
 
-                let val1 = params[0].get_value(&mut self.program);
+                let val1 = params[0].get_value(&mut self.program)?;
 
-                self.program.relative_base += val1 as usize;
+                let relative_base = val1.to_i64().and_then(|val1| self.program.relative_base.checked_add(val1));
+                self.program.relative_base = relative_base.ok_or(self.overflow_error())?;
 
                 index += oc.param_count as usize;
                 Status::Ready
@@ -207,78 +589,195 @@ impl IntComp {
             Instruction::Halt => {
                 Status::Halted
             }
+            Instruction::Extension(opcode, oc) => {
+                let params = Param::get_params(&self.program, &index, oc)?;
+
+                let mut written = Vec::new();
+                let written = undo.as_mut().map_or(&mut written, |undo| &mut undo.writes);
+                let (status, next) = self.extensions.execute(&mut self.program, *opcode, params, index, written)?;
+
+                index = next;
+                status
+            }
         };
-        self.program.oc = opcode;
-        self.program.status = status;
+        if let (Some(tracer), Some((params, operands))) = (self.tracer.as_mut(), traced) {
+            if status != Status::RequestedInput {
+                tracer.trace(&TraceEvent {
+                    index: self.program.index,
+                    instruction: &inst,
+                    params: &params,
+                    operands: &operands,
+                    write: self.program.last_write.clone(),
+                });
+            }
+        }
+        if let (Some(profile), Some(reads)) = (self.profile.as_mut(), reads) {
+            if status != Status::RequestedInput {
+                let write = self.program.last_write.as_ref().map(|(address, _)| *address);
+                profile.record(self.program.index, &inst, &reads, write);
+            }
+        }
+        if let Some(transcript) = self.transcript.as_mut() {
+            let (executed, index) = (self.program.executed, self.program.index);
+            match (&inst, &status, &self.program.last_write) {
+                (Instruction::Input(_), Status::Ready, Some((_, value))) =>
+                    transcript.events.push(TranscriptEvent::Input { executed, index, value: value.clone() }),
+                (_, Status::Outputed(value), _) =>
+                    transcript.events.push(TranscriptEvent::Output { executed, index, value: value.clone() }),
+                _ => {},
+            }
+        }
+        if let (Some(history), Some(mut undo)) = (self.history.as_mut(), undo) {
+            if status != Status::RequestedInput {
+                if let (Instruction::Input(_), Some((_, value))) = (&inst, &self.program.last_write) {
+                    undo.input = Some(value.clone());
+                }
+                history.push(undo);
+            }
+        }
+        if status != Status::RequestedInput {
+            self.program.executed += 1;
+        }
+        self.program.status = status.clone();
         self.program.index = index;
         
-        status
+        Ok(status)
     }
-    
-}
 
-pub fn get_program_from_file() -> Option<Vec<i64>> {
-    let args : Vec<String> = env::args().collect();
-    println!("{:?}", args);
+    fn peek_operands(&self, inst: &Instruction, index: usize) -> (Params<W>, Vec<W>) {
+        let params = inst.opcode()
+            .and_then(|oc| Param::get_params(&self.program, &index, oc).ok())
+            .unwrap_or_default();
+        let operands = params.iter().map(|param| param.peek_value(&self.program)).collect();
+
+        (params, operands)
+    }
 
-    let path = &args.get(1).expect("Supply path param");
-    println!("{}", path);
+    /// The state to return to when undoing the instruction about to execute.
+    fn undo_entry(&self, inst: &Instruction, index: usize) -> Undo<W> {
+        let writes = inst.write_param().and_then(|write_param| {
+            let params = Param::get_params(&self.program, &index, inst.opcode()?).ok()?;
+            let address = params[write_param].resolve(&self.program)?;
+            Some((address, self.program.memory.get(address)))
+        });
 
-    let path = path::Path::new(path);
-    if !path.exists() {
-        println!("Path unreachable");
-        return None;
+        Undo {
+            index: self.program.index,
+            relative_base: self.program.relative_base,
+            status: self.program.status.clone(),
+            executed: self.program.executed,
+            writes: writes.into_iter().collect(),
+            input: None,
+            dense_len: self.program.memory.as_slice().len(),
+        }
     }
 
-    let file = fs::read_to_string(path).ok()?;
+    /// Addresses of the parameters the instruction is about to read from memory.
+    fn read_addresses(&self, inst: &Instruction, index: usize) -> Vec<usize> {
+        let params = inst.opcode()
+            .and_then(|oc| Param::get_params(&self.program, &index, oc).ok())
+            .unwrap_or_default();
 
-    let code : Vec<i64> = file.split(",").map(|line| line.trim().parse::<i64>().unwrap_or_default()).collect();
-    
-    Some(code)
+        params.iter().enumerate()
+            .filter(|(i, _)| inst.write_param() != Some(*i))
+            .filter_map(|(_, param)| param.resolve(&self.program))
+            .collect()
+    }
+
+    fn arithmetic<T>(&self, result: Option<T>) -> Result<T, IntCompError> {
+        result.ok_or_else(|| match self.overflow {
+            Overflow::Promote => IntCompError::WordTooNarrow { index: self.program.index, word: self.program.current_word() },
+            _ => self.overflow_error(),
+        })
+    }
+
+    /// The relative base is an `i64` whatever the word type, so promoting does not help it.
+    fn overflow_error(&self) -> IntCompError {
+        IntCompError::Overflow { index: self.program.index, word: self.program.current_word() }
+    }
+
+    fn jump_target(&self, address: &W) -> Result<usize, IntCompError> {
+        self.program.word_address(address)
+    }
+}
+
+/// Runs `program` reading one number per line from `input` and writing each output on its own line,
+/// leaving the final memory in `program`. A bad input line or a failed write stops the run with
+/// `StreamError::Io`.
+pub fn process_stream<T: BufRead, U: Write>(program: &mut Vec<i64>, input: &mut T, output: &mut U) -> Result<Status, StreamError> {
+    let mut int_comp = IntComp::new(program);
+    let mut io = StreamIo::new(input, output);
+    let status = int_comp.run_with_io(&mut io);
+    *program = int_comp.get_program();
+
+    let status = status?;
+    match io.take_error() {
+        Some(error) => Err(StreamError::Io(error)),
+        None => Ok(status),
+    }
+}
+
+/// Loads the program named by the first command line argument, printing why if it cannot.
+pub fn get_program_from_file() -> Option<Vec<i64>> {
+    let path = env::args().nth(1).expect("Supply path param");
+
+    match loader::load_program(path::Path::new(&path)) {
+        Ok(program) => Some(program),
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            None
+        }
+    }
+}
+
+pub fn get_program_from_path(path: &path::Path) -> Result<Vec<i64>, loader::LoadError> {
+    loader::load_program(path)
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use io::QueueIo;
+    use std::time::Duration;
 
     #[test]
     fn extend_memory_to_5_extends_to_len_6() {
         let mut program = Program::new(vec![1]);
-        program.extend_memory_to(5);
+        program.memory.extend_to(5);
 
         assert_eq!(program.memory.len(), 6);
     }
 
     #[test]
     fn int_comp_adds() {
-        let program = vec![10001, 1,1,0, 99];
+        let program = vec![1, 1,1,3, 99];
         let mut int_comp = IntComp::new(&program);
 
-        int_comp.run();
+        int_comp.run().unwrap();
 
-        assert_eq!(int_comp.get_program(), vec![10001, 1,1,2, 99])
+        assert_eq!(int_comp.get_program(), vec![1, 1,1,2, 99])
     }
 
     #[test]
     fn int_comp_multiplies() {
-        let program = vec![10002, 1,1,0, 99];
+        let program = vec![2, 1,1,3, 99];
         let mut int_comp = IntComp::new(&program);
 
-        int_comp.run();
+        int_comp.run().unwrap();
 
-        assert_eq!(int_comp.get_program(), vec![10002, 1,1,1, 99])
+        assert_eq!(int_comp.get_program(), vec![2, 1,1,1, 99])
     }
 
     #[test]
     fn int_comp_resets() {
-        let program = vec![10001, 1,1,0, 99];
+        let program = vec![1, 1,1,3, 99];
         let mut int_comp = IntComp::new(&program);
 
-        let status = int_comp.run();
+        let status = int_comp.run().unwrap();
 
         assert_eq!(status, Status::Halted);
-        assert_eq!(int_comp.get_program(), vec![10001, 1,1,2, 99]);
+        assert_eq!(int_comp.get_program(), vec![1, 1,1,2, 99]);
 
         let status = int_comp.reset();
 
@@ -291,32 +790,369 @@ mod tests {
         let program = vec![104, 5, 99];
         let mut int_comp = IntComp::new(&program);
 
-        let status = int_comp.run();
+        let status = int_comp.run().unwrap();
 
         assert_eq!(int_comp.get_program(), vec![104, 5, 99]);
         assert_eq!(status, Status::Outputed(5));
 
-        let status = int_comp.run();
+        let status = int_comp.run().unwrap();
 
         assert_eq!(status, Status::Halted);
     }
 
     #[test]
     fn int_comp_inputs() {
-        let program = vec![103, 5, 99];
+        let program = vec![3, 1, 99];
         let mut int_comp = IntComp::new(&program);
 
-        let status = int_comp.run();
+        let status = int_comp.run().unwrap();
 
         assert_eq!(status, Status::RequestedInput);
 
-        let status = int_comp.run();
+        let status = int_comp.run().unwrap();
 
         assert_eq!(status, Status::RequestedInput);
 
-        let status = int_comp.run_with_input(13);
+        let status = int_comp.run_with_input(13).unwrap();
 
         assert_eq!(status, Status::Halted);
-        assert_eq!(int_comp.get_program(), vec![103, 13, 99]);
+        assert_eq!(int_comp.get_program(), vec![3, 13, 99]);
+    }
+
+    #[test]
+    fn int_comp_consumes_preloaded_inputs() {
+        let program = vec![3, 9, 3, 10, 1, 9, 10, 11, 99, 0, 0, 0];
+        let mut int_comp = IntComp::new(&program);
+
+        int_comp.extend_input(vec![5, 7]);
+        let status = int_comp.run().unwrap();
+
+        assert_eq!(status, Status::Halted);
+        assert_eq!(int_comp.get_program()[9..], [5, 7, 12]);
+    }
+
+    #[test]
+    fn int_comp_buffers_input_before_request() {
+        let program = vec![104, 1, 3, 7, 4, 7, 99, 0];
+        let mut int_comp = IntComp::new(&program);
+
+        int_comp.push_input(13);
+        let status = int_comp.run().unwrap();
+        assert_eq!(status, Status::Outputed(1));
+
+        let status = int_comp.run().unwrap();
+        assert_eq!(status, Status::Outputed(13));
+    }
+
+    #[test]
+    fn int_comp_steps_one_instruction() {
+        let program = vec![1101, 2, 3, 7, 4, 7, 99, 0];
+        let mut int_comp = IntComp::new(&program);
+
+        let status = int_comp.step().unwrap();
+
+        assert_eq!(status, Status::Ready);
+        assert_eq!(int_comp.index(), 4);
+        assert_eq!(int_comp.peek(7), 5);
+
+        let status = int_comp.step().unwrap();
+
+        assert_eq!(status, Status::Outputed(5));
+        assert_eq!(int_comp.index(), 6);
+    }
+
+    #[test]
+    fn int_comp_decode_cache_sees_self_modification() {
+        // Outputs 7, overwrites its first instruction with HLT and jumps back to it.
+        let program = vec![104, 7, 1101, 99, 0, 0, 1105, 1, 0];
+
+        for cache in [true, false] {
+            let mut int_comp = IntComp::new(&program);
+            int_comp.set_decode_cache(cache);
+
+            assert_eq!(int_comp.run().unwrap(), Status::Outputed(7));
+            assert_eq!(int_comp.run().unwrap(), Status::Halted);
+        }
+    }
+
+    #[test]
+    fn int_comp_runs_for_a_budget() {
+        // Jumps to itself forever.
+        let mut int_comp = IntComp::new(&[1105, 1, 0]);
+
+        assert_eq!(int_comp.run_for(10).unwrap(), Status::BudgetExhausted);
+        assert_eq!(int_comp.executed(), 10);
+        assert_eq!(int_comp.run_until(Instant::now() + Duration::from_millis(10)).unwrap(), Status::BudgetExhausted);
+        assert!(int_comp.executed() > 10);
+    }
+
+    #[test]
+    fn int_comp_run_for_zero_reports_waiting_for_input() {
+        let mut int_comp = IntComp::new(&[3, 3, 99, 0]);
+
+        assert_eq!(int_comp.run_for(0).unwrap(), Status::BudgetExhausted);
+        assert_eq!(int_comp.run_for(1).unwrap(), Status::RequestedInput);
+        assert_eq!(int_comp.run_for(0).unwrap(), Status::RequestedInput);
+
+        int_comp.push_input(5);
+        assert_eq!(int_comp.run_for(0).unwrap(), Status::BudgetExhausted);
+    }
+
+    #[test]
+    fn int_comp_run_for_stops_at_output() {
+        let mut int_comp = IntComp::new(&[104, 7, 99]);
+
+        assert_eq!(int_comp.run_for(10).unwrap(), Status::Outputed(7));
+        assert_eq!(int_comp.run_for(10).unwrap(), Status::Halted);
+        assert_eq!(int_comp.executed(), 2);
+        assert_eq!(int_comp.run_for(0).unwrap(), Status::Halted);
+        assert_eq!(int_comp.run_until(Instant::now()).unwrap(), Status::Halted);
+
+        int_comp.reset();
+        assert_eq!(int_comp.executed(), 0);
+    }
+
+    #[test]
+    fn int_comp_pokes_memory() {
+        let program = vec![4, 3, 99, 0];
+        let mut int_comp = IntComp::new(&program);
+
+        int_comp.poke(3, 8);
+        int_comp.poke(6, 1);
+
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(8));
+        assert_eq!(int_comp.peek_range(2, 6), vec![99, 8, 0, 0, 1, 0]);
+
+        int_comp.poke(usize::MAX, 5);
+        assert_eq!(int_comp.peek_range(usize::MAX - 1, 5), vec![0, 5]);
+    }
+
+    #[test]
+    fn process_stream_reads_and_writes_lines() {
+        let mut program = vec![3, 9, 1002, 9, 2, 9, 4, 9, 99, 0];
+        let mut output_buf: Vec<u8> = Vec::new();
+
+        let status = process_stream(&mut program, &mut "21\n".as_bytes(), &mut output_buf).unwrap();
+
+        assert_eq!(status, Status::Halted);
+        assert_eq!(program[9], 42);
+        assert_eq!(output_buf, b"42\n");
+    }
+
+    #[test]
+    fn process_stream_returns_input_errors() {
+        let mut program = vec![3, 9, 1002, 9, 2, 9, 4, 9, 99, 0];
+        let mut output_buf: Vec<u8> = Vec::new();
+
+        let error = process_stream(&mut program, &mut "2x\n".as_bytes(), &mut output_buf).unwrap_err();
+
+        assert!(matches!(&error, StreamError::Io(error) if error.kind() == std::io::ErrorKind::InvalidData));
+        assert_eq!(error.to_string(), "line 1: 2x is not a number");
+    }
+
+    #[test]
+    fn int_comp_rejects_write_to_immediate() {
+        let program = vec![10001, 1,1,0, 99];
+        let mut int_comp = IntComp::new(&program);
+
+        let error = int_comp.run().unwrap_err();
+
+        assert_eq!(error, IntCompError::WriteToImmediate { index: 0, word: 10001 });
+        assert_eq!(int_comp.get_program(), program);
+    }
+
+    #[test]
+    fn int_comp_rejects_unknown_opcode() {
+        let program = vec![104, 5, 42, 99];
+        let mut int_comp = IntComp::new(&program);
+
+        let status = int_comp.run().unwrap();
+        assert_eq!(status, Status::Outputed(5));
+
+        let error = int_comp.run().unwrap_err();
+        assert_eq!(error, IntCompError::UnknownOpcode { index: 2, word: 42 });
+    }
+
+    #[test]
+    fn int_comp_rejects_negative_address() {
+        let program = vec![204, -1, 99];
+        let mut int_comp = IntComp::new(&program);
+
+        let error = int_comp.run().unwrap_err();
+
+        assert_eq!(error, IntCompError::NegativeAddress { index: 0, word: 204, address: -1 });
+    }
+
+    #[test]
+    fn int_comp_writes_far_address() {
+        let program = vec![21101, 7, 0, 0, 204, 0, 99];
+        let mut int_comp = IntComp::new(&program);
+        int_comp.program.relative_base = 1 << 40;
+
+        let status = int_comp.run().unwrap();
+
+        assert_eq!(status, Status::Outputed(7));
+        assert_eq!(int_comp.peek(1 << 40), 7);
+        assert_eq!(int_comp.get_program(), program);
+    }
+
+    #[test]
+    fn int_comp_rejects_address_past_max() {
+        let program = vec![1101, 1, 1, 100000, 99];
+        let mut int_comp = IntComp::new(&program);
+        int_comp.set_max_address(Some(4096));
+
+        let error = int_comp.run().unwrap_err();
+
+        assert_eq!(error, IntCompError::AddressTooLarge { index: 0, word: 1101, address: 100000 });
+    }
+
+    #[test]
+    fn int_comp_rejects_jump_out_of_range() {
+        let program = vec![1105, 1, 7, 99];
+        let mut int_comp = IntComp::new(&program);
+
+        let error = int_comp.run().unwrap_err();
+
+        assert_eq!(error, IntCompError::IndexOutOfRange { index: 7 });
+    }
+
+    #[test]
+    fn int_comp_rejects_overflow_by_default() {
+        let mut int_comp = IntComp::new(&[1101, i64::MAX, 1, 5, 99, 0]);
+
+        assert_eq!(int_comp.run().unwrap_err(), IntCompError::Overflow { index: 0, word: 1101 });
+    }
+
+    #[test]
+    fn int_comp_applies_overflow_policy() {
+        let program = [1102, i64::MAX, 2, 7, 4, 7, 99, 0];
+
+        let mut int_comp = IntComp::new(&program);
+        int_comp.set_overflow(Overflow::Wrap);
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(-2));
+
+        let mut int_comp = IntComp::new(&program);
+        int_comp.set_overflow(Overflow::Saturate);
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(i64::MAX));
+    }
+
+    #[test]
+    fn int_comp_promotes_to_wider_words() {
+        let mut int_comp = IntComp::new(&[3, 9, 1002, 9, 4, 9, 4, 9, 99, 0]);
+        int_comp.set_overflow(Overflow::Promote);
+        int_comp.push_input(i64::MAX);
+
+        assert_eq!(int_comp.run().unwrap_err(), IntCompError::WordTooNarrow { index: 2, word: 1002 });
+        let mut wide = int_comp.promote::<i128>();
+
+        assert_eq!(wide.run().unwrap(), Status::Outputed(i128::from(i64::MAX) * 4));
+        assert_eq!(wide.executed(), 3);
+        wide.reset();
+        assert_eq!(wide.get_program(), vec![3, 9, 1002, 9, 4, 9, 4, 9, 99, 0]);
+    }
+
+    #[test]
+    fn int_comp_runs_on_i128_words() {
+        let big = i128::from(i64::MAX);
+        let mut int_comp = IntComp::<i128>::from_words(&[1102, big, 4, 7, 4, 7, 99, 0]);
+
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(big * 4));
+        assert_eq!(int_comp.run().unwrap(), Status::Halted);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn int_comp_runs_on_big_words() {
+        use num_bigint::BigInt;
+
+        let big: BigInt = "1000000000000000000000000000000".parse().unwrap();
+        let program: Vec<BigInt> = [2, 7, 7, 7, 4, 7, 99].into_iter().map(BigInt::from).chain([big.clone()]).collect();
+        let mut int_comp = IntComp::from_words(&program);
+
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(&big * &big));
+    }
+
+    #[test]
+    fn int_comp_adjusts_relative_base_down() {
+        let mut int_comp = IntComp::new(&[109, 10, 109, -4, 204, 0, 99]);
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(99));
+        assert_eq!(int_comp.relative_base(), 6);
+
+        let mut int_comp = IntComp::new(&[109, -5, 204, 9, 99]);
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(99));
+        assert_eq!(int_comp.relative_base(), -5);
+    }
+
+    #[test]
+    fn int_comp_runs_with_io() {
+        let program = vec![3, 9, 1001, 9, 1, 9, 4, 9, 99, 0];
+        let mut int_comp = IntComp::new(&program);
+        let mut io = QueueIo::new(&[41]);
+
+        let status = int_comp.run_with_io(&mut io).unwrap();
+
+        assert_eq!(status, Status::Halted);
+        assert_eq!(io.output, vec![42]);
+    }
+
+    #[test]
+    fn int_comp_run_with_io_stops_without_input() {
+        let program = vec![3, 5, 4, 5, 99, 0];
+        let mut int_comp = IntComp::new(&program);
+        let mut io = QueueIo::default();
+
+        let status = int_comp.run_with_io(&mut io).unwrap();
+        assert_eq!(status, Status::RequestedInput);
+
+        io.input.push_back(7);
+        let status = int_comp.run_with_io(&mut io).unwrap();
+
+        assert_eq!(status, Status::Halted);
+        assert_eq!(io.output, vec![7]);
+    }
+
+    #[test]
+    fn int_comp_steps_back_to_start() {
+        let program = vec![3, 9, 1001, 9, 1, 9, 4, 9, 99, 0];
+        let mut int_comp = IntComp::new(&program);
+        int_comp.set_history_limit(Some(10));
+        int_comp.push_input(41);
+        int_comp.run().unwrap();
+        int_comp.run().unwrap();
+
+        assert_eq!(int_comp.last_writer(9), Some(2));
+        while int_comp.step_back() {}
+
+        assert_eq!(int_comp.get_program(), program);
+        assert_eq!(int_comp.index(), 0);
+        assert_eq!(int_comp.executed(), 0);
+        assert_eq!(int_comp.run().unwrap(), Status::Outputed(42));
+    }
+
+    #[test]
+    fn int_comp_step_back_shrinks_grown_memory() {
+        let program = vec![1101, 1, 1, 100, 99];
+        let mut int_comp = IntComp::new(&program);
+        int_comp.set_history_limit(Some(2));
+        int_comp.run().unwrap();
+
+        assert_eq!(int_comp.get_program().len(), 101);
+        while int_comp.step_back() {}
+        assert_eq!(int_comp.get_program(), program);
+    }
+
+    #[test]
+    fn int_comp_history_is_bounded() {
+        let program = vec![1101, 0, 0, 0, 1101, 0, 0, 0, 1101, 0, 0, 0, 99];
+        let mut int_comp = IntComp::new(&program);
+        int_comp.set_history_limit(Some(2));
+        int_comp.run().unwrap();
+
+        assert_eq!(int_comp.history_len(), 2);
+        assert!(int_comp.step_back());
+        assert!(int_comp.step_back());
+        assert!(!int_comp.step_back());
+        assert_eq!(int_comp.index(), 8);
     }
 }
\ No newline at end of file
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::cfg::{ControlFlowGraph, Edge};
+use crate::instruction::{Instruction, param::{Param, ParamMode}};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug)]
+pub enum LintKind {
+    /// The instruction writes through an immediate parameter, which fails at run time.
+    ImmediateWrite,
+    /// A position-mode write lands inside the reachable instruction at `instruction`.
+    WriteIntoCode { target: usize, instruction: usize },
+    /// An immediate jump target that is negative or does not decode.
+    BadJumpTarget { target: i64 },
+    /// A word reached by running on from the previous instruction that does not decode.
+    UnknownOpcode { word: i64 },
+    /// Mode digits for parameters the opcode does not have.
+    ExtraModeDigits { word: i64, param_count: u8 },
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug)]
+pub struct Lint {
+    pub address: usize,
+    pub kind: LintKind,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            LintKind::ImmediateWrite =>
+                write!(f, "{}: writes to immediate parameter", self.address),
+            LintKind::WriteIntoCode { target, instruction } =>
+                write!(f, "{}: writes [{}] inside the instruction at {}", self.address, target, instruction),
+            LintKind::BadJumpTarget { target } =>
+                write!(f, "{}: jumps to {}, which does not decode", self.address, target),
+            LintKind::UnknownOpcode { word } =>
+                write!(f, "{}: reachable word {} does not decode", self.address, word),
+            LintKind::ExtraModeDigits { word, param_count } =>
+                write!(f, "{}: {} has modes for more than {} parameters", self.address, word, param_count),
+        }
+    }
+}
+
+/// Checks the code reachable from address 0 for likely mistakes, ordered by address.
+pub fn lint(program: &[i64]) -> Vec<Lint> {
+    let cfg = ControlFlowGraph::build(program);
+    let mut lints = Vec::new();
+
+    let mut code = BTreeMap::new();
+    for line in cfg.blocks.iter().flat_map(|block| &block.lines) {
+        for offset in 0..line.words.len() {
+            code.insert(line.address + offset, line.address);
+        }
+    }
+
+    for block in &cfg.blocks {
+        for line in &block.lines {
+            lint_instruction(program, line.address, &code, &mut lints);
+        }
+
+        let last = block.lines.last().map_or(block.start, |line| line.address);
+        for edge in &block.edges {
+            match *edge {
+                Edge::Jump(target) if cfg.invalid.contains(&target) =>
+                    lints.push(Lint { address: last, kind: LintKind::BadJumpTarget { target: target as i64 } }),
+                Edge::Next(next) if cfg.invalid.contains(&next) => {
+                    let word = program.get(next).copied().unwrap_or_default();
+                    lints.push(Lint { address: next, kind: LintKind::UnknownOpcode { word } });
+                },
+                _ => {},
+            }
+        }
+    }
+
+    lints.sort_by_key(|lint| lint.address);
+    lints.dedup();
+    lints
+}
+
+fn lint_instruction(program: &[i64], address: usize, code: &BTreeMap<usize, usize>, lints: &mut Vec<Lint>) {
+    let word = program[address];
+    let Ok(inst) = Instruction::new(address, &word) else {
+        return;
+    };
+    let Some(oc) = inst.opcode() else {
+        if word >= 100 {
+            lints.push(Lint { address, kind: LintKind::ExtraModeDigits { word, param_count: 0 } });
+        }
+        return;
+    };
+    let Some(params) = Param::decode(program, address + 1, oc) else {
+        return;
+    };
+
+    if word / 10_i64.pow(2 + oc.param_count as u32) != 0 {
+        lints.push(Lint { address, kind: LintKind::ExtraModeDigits { word, param_count: oc.param_count } });
+    }
+
+    if let Some(write_param) = inst.write_param() {
+        let param = &params[write_param];
+        match param.config {
+            ParamMode::Immediate => lints.push(Lint { address, kind: LintKind::ImmediateWrite }),
+            ParamMode::Position => {
+                let instruction = usize::try_from(param.value).ok()
+                    .and_then(|target| code.get(&target).map(|&instruction| (target, instruction)));
+                if let Some((target, instruction)) = instruction {
+                    lints.push(Lint { address, kind: LintKind::WriteIntoCode { target, instruction } });
+                }
+            },
+            ParamMode::Relative => {},
+        }
+    }
+
+    if let Instruction::JumpTrue(_) | Instruction::JumpFalse(_) = inst {
+        if params[1].config == ParamMode::Immediate && params[1].value < 0 {
+            lints.push(Lint { address, kind: LintKind::BadJumpTarget { target: params[1].value } });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(program: &[i64]) -> Vec<LintKind> {
+        lint(program).into_iter().map(|lint| lint.kind).collect()
+    }
+
+    #[test]
+    fn lint_accepts_clean_program() {
+        let program = vec![4, 20, 1001, 20, -1, 20, 1005, 20, 0, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3];
+
+        assert_eq!(lint(&program), vec![]);
+    }
+
+    #[test]
+    fn lint_reports_immediate_and_code_writes() {
+        let program = vec![11101, 1, 2, 3, 3, 1, 99];
+
+        assert_eq!(kinds(&program), vec![
+            LintKind::ImmediateWrite,
+            LintKind::WriteIntoCode { target: 1, instruction: 0 },
+        ]);
+    }
+
+    #[test]
+    fn lint_reports_bad_jumps_and_unknown_opcodes() {
+        let program = vec![1005, 9, 8, 1105, 1, -4, 104, 0, 42];
+        let lints = lint(&program);
+
+        assert_eq!(lints, vec![
+            Lint { address: 0, kind: LintKind::BadJumpTarget { target: 8 } },
+            Lint { address: 3, kind: LintKind::BadJumpTarget { target: -4 } },
+        ]);
+        assert_eq!(lints[1].to_string(), "3: jumps to -4, which does not decode");
+
+        assert_eq!(kinds(&[104, 1, 42]), vec![LintKind::UnknownOpcode { word: 42 }]);
+    }
+
+    #[test]
+    fn lint_reports_extra_mode_digits() {
+        let program = vec![10104, 1, 1099];
+
+        assert_eq!(kinds(&program), vec![
+            LintKind::ExtraModeDigits { word: 10104, param_count: 1 },
+            LintKind::ExtraModeDigits { word: 1099, param_count: 0 },
+        ]);
+    }
+}
@@ -0,0 +1,205 @@
+use std::{error::Error, fmt, fs, io::{self, Read}, mem, path::Path};
+
+/// A line holding only this splits a file into several programs.
+pub const PROGRAM_SEPARATOR: &str = "---";
+
+const COMMENT: char = '#';
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// `line` and `column` are 1-based and point at the start of `token`, or at the comma of an
+    /// empty field, whose `token` is empty.
+    Parse { line: usize, column: usize, token: String },
+    ProgramCount(usize),
+    GzipUnsupported,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "{}", error),
+            LoadError::Parse { line, column, token } if token.is_empty() =>
+                write!(f, "missing value before ',' (line {}, column {})", line, column),
+            LoadError::Parse { line, column, token } =>
+                write!(f, "'{}' is not a number (line {}, column {})", token, line, column),
+            LoadError::ProgramCount(count) => write!(f, "expected one program, found {}", count),
+            LoadError::GzipUnsupported => write!(f, "gzip input needs the \"gzip\" feature"),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+/// Parses every program in `source`. Values are separated by commas, whitespace or newlines, `#`
+/// starts a comment running to the end of the line, and programs are separated by `---` lines.
+pub fn parse_programs(source: &str) -> Result<Vec<Vec<i64>>, LoadError> {
+    let mut programs = Vec::new();
+    let mut program = Vec::new();
+    // A comma with no value since the previous one, e.g. `1,,2`, would shift every later address.
+    let mut after_comma = false;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let code = line.split(COMMENT).next().unwrap_or_default();
+        if code.trim() == PROGRAM_SEPARATOR {
+            programs.push(mem::take(&mut program));
+            after_comma = false;
+            continue;
+        }
+
+        let mut token_start = None;
+        for (column, char) in code.char_indices().chain([(code.len(), '\n')]) {
+            if char != ',' && !char.is_whitespace() {
+                token_start.get_or_insert(column);
+                continue;
+            }
+            if let Some(start) = token_start.take() {
+                let token = &code[start..column];
+                let value = token.parse().map_err(|_| LoadError::Parse {
+                    line: line_index + 1,
+                    column: code[..start].chars().count() + 1,
+                    token: token.to_string(),
+                })?;
+                program.push(value);
+                after_comma = false;
+            }
+            if char == ',' {
+                if after_comma || program.is_empty() {
+                    return Err(LoadError::Parse {
+                        line: line_index + 1,
+                        column: code[..column].chars().count() + 1,
+                        token: String::new(),
+                    });
+                }
+                after_comma = true;
+            }
+        }
+    }
+    programs.push(program);
+    programs.retain(|program| !program.is_empty());
+
+    Ok(programs)
+}
+
+/// Parses `source`, which must hold exactly one program.
+pub fn parse_program(source: &str) -> Result<Vec<i64>, LoadError> {
+    let mut programs = parse_programs(source)?;
+    match programs.len() {
+        1 => Ok(programs.remove(0)),
+        count => Err(LoadError::ProgramCount(count)),
+    }
+}
+
+/// Reads every program from `input`, which may be gzip compressed.
+pub fn read_programs(input: impl Read) -> Result<Vec<Vec<i64>>, LoadError> {
+    parse_programs(&read_source(input)?)
+}
+
+pub fn read_program(input: impl Read) -> Result<Vec<i64>, LoadError> {
+    parse_program(&read_source(input)?)
+}
+
+pub fn load_programs(path: &Path) -> Result<Vec<Vec<i64>>, LoadError> {
+    read_programs(fs::File::open(path)?)
+}
+
+pub fn load_program(path: &Path) -> Result<Vec<i64>, LoadError> {
+    read_program(fs::File::open(path)?)
+}
+
+fn read_source(mut input: impl Read) -> Result<String, LoadError> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes)?;
+    if bytes.starts_with(&GZIP_MAGIC) {
+        bytes = gunzip(&bytes)?;
+    }
+
+    String::from_utf8(bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error).into())
+}
+
+#[cfg(feature = "gzip")]
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, LoadError> {
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(bytes).read_to_end(&mut decoded)?;
+
+    Ok(decoded)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gunzip(_bytes: &[u8]) -> Result<Vec<u8>, LoadError> {
+    Err(LoadError::GzipUnsupported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commas_whitespace_and_comments() {
+        let source = "1,0, 0,3  # add\n99\n\t4,\n";
+
+        assert_eq!(parse_program(source).unwrap(), vec![1, 0, 0, 3, 99, 4]);
+    }
+
+    #[test]
+    fn reports_bad_token_position() {
+        let error = parse_program("1,2,3\n4, 5x,6\n").unwrap_err();
+
+        match error {
+            LoadError::Parse { line, column, token } => assert_eq!((line, column, token.as_str()), (2, 4, "5x")),
+            error => panic!("unexpected {:?}", error),
+        }
+    }
+
+    #[test]
+    fn rejects_empty_fields() {
+        let position = |source| match parse_program(source).unwrap_err() {
+            LoadError::Parse { line, column, token } if token.is_empty() => (line, column),
+            error => panic!("unexpected {:?}", error),
+        };
+
+        assert_eq!(position("1,,2"), (1, 3));
+        assert_eq!(position(",1,2"), (1, 1));
+        assert_eq!(position("1,\n, 2"), (2, 1));
+        assert_eq!(parse_program("1,\n2,\n").unwrap(), vec![1, 2]);
+        assert_eq!(parse_program("1,,2").unwrap_err().to_string(), "missing value before ',' (line 1, column 3)");
+    }
+
+    #[test]
+    fn splits_multiple_programs() {
+        let source = "# first\n1,2\n---\n3,4\n---\n";
+
+        assert_eq!(parse_programs(source).unwrap(), vec![vec![1, 2], vec![3, 4]]);
+        assert!(matches!(parse_program(source), Err(LoadError::ProgramCount(2))));
+    }
+
+    #[test]
+    fn reads_from_reader() {
+        assert_eq!(read_program("104,7,99\n".as_bytes()).unwrap(), vec![104, 7, 99]);
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn rejects_gzip_without_feature() {
+        assert!(matches!(read_program(&[0x1f, 0x8b, 8, 0][..]), Err(LoadError::GzipUnsupported)));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn reads_gzip() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"104,7,99\n").unwrap();
+        let bytes = encoder.finish().unwrap();
+
+        assert_eq!(read_program(bytes.as_slice()).unwrap(), vec![104, 7, 99]);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+use crate::word::Word;
+
+pub const PAGE_SIZE: usize = 1024;
+
+/// How far past the loaded image the dense vector may grow before writes go to pages.
+const DENSE_SLACK: usize = 1 << 16;
+
+/// Program memory: a dense vector covering the loaded image and the cells just past it, and pages
+/// allocated on demand for everything further out. Unwritten cells read as 0.
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct Memory<W = i64> {
+    dense: Vec<W>,
+    dense_limit: usize,
+    pages: BTreeMap<usize, Box<[W]>>,
+}
+
+impl Memory {
+    pub fn new(image: Vec<i64>) -> Self {
+        Memory::from_words(image)
+    }
+}
+
+impl<W: Word> Memory<W> {
+    pub fn from_words(image: Vec<W>) -> Self {
+        let dense_limit = image.len() + DENSE_SLACK;
+        Memory { dense: image, dense_limit, pages: BTreeMap::new() }
+    }
+
+    pub fn get(&self, address: usize) -> W {
+        self.fetch(address).cloned().unwrap_or_default()
+    }
+
+    /// Returns the cell if it was loaded or written, `None` if it was never touched.
+    pub fn fetch(&self, address: usize) -> Option<&W> {
+        if address < self.dense_limit {
+            return self.dense.get(address);
+        }
+
+        self.pages.get(&(address / PAGE_SIZE)).map(|page| &page[address % PAGE_SIZE])
+    }
+
+    pub fn set(&mut self, address: usize, value: W) {
+        if address < self.dense_limit {
+            if self.dense.len() <= address {
+                self.extend_to(address);
+            }
+            self.dense[address] = value;
+            return;
+        }
+
+        let page = self.pages.entry(address / PAGE_SIZE)
+            .or_insert_with(|| vec![W::default(); PAGE_SIZE].into_boxed_slice());
+        page[address % PAGE_SIZE] = value;
+    }
+
+    pub fn extend_to(&mut self, size: usize) {
+        if self.dense.len() <= size {
+            self.dense.resize(size + 1, W::default());
+        }
+    }
+
+    /// Drops the dense cells from `len` on, as if they were never written.
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.dense.truncate(len);
+    }
+
+    /// One past the highest cell that was loaded or written, or `usize::MAX` once the page holding
+    /// `usize::MAX` is written.
+    pub fn len(&self) -> usize {
+        match self.pages.keys().next_back() {
+            Some(page) => (page + 1).saturating_mul(PAGE_SIZE),
+            None => self.dense.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The loaded image and the cells grown next to it.
+    pub fn as_slice(&self) -> &[W] {
+        &self.dense
+    }
+
+    pub fn pages(&self) -> impl Iterator<Item = (usize, &[W])> {
+        self.pages.iter().map(|(page, words)| (page * PAGE_SIZE, &words[..]))
+    }
+
+    /// Copies every cell onto the wider word type `V`.
+    pub(crate) fn promote<V: Word + From<W>>(&self) -> Memory<V> {
+        Memory {
+            dense: self.dense.iter().cloned().map(V::from).collect(),
+            dense_limit: self.dense_limit,
+            pages: self.pages.iter()
+                .map(|(&page, words)| (page, words.iter().cloned().map(V::from).collect()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn dense_limit(&self) -> usize {
+        self.dense_limit
+    }
+
+    pub(crate) fn from_parts(dense: Vec<W>, dense_limit: usize, pages: Vec<(usize, Vec<W>)>) -> Option<Self> {
+        if dense.len() > dense_limit {
+            return None;
+        }
+
+        let mut memory = Memory { dense, dense_limit, pages: BTreeMap::new() };
+        for (start, words) in pages {
+            if start % PAGE_SIZE != 0 || words.len() != PAGE_SIZE || start < memory.dense_limit {
+                return None;
+            }
+            memory.pages.insert(start / PAGE_SIZE, words.into_boxed_slice());
+        }
+
+        Some(memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_reads_zero_when_untouched() {
+        let memory = Memory::new(vec![1, 2]);
+
+        assert_eq!(memory.get(1), 2);
+        assert_eq!(memory.get(2), 0);
+        assert_eq!(memory.get(1 << 50), 0);
+        assert_eq!(memory.fetch(2), None);
+    }
+
+    #[test]
+    fn memory_pages_far_writes() {
+        let mut memory = Memory::new(vec![1, 2]);
+
+        memory.set(1_000_000_000_000, 7);
+
+        assert_eq!(memory.get(1_000_000_000_000), 7);
+        assert_eq!(memory.as_slice(), &[1, 2]);
+        assert_eq!(memory.pages().count(), 1);
+        assert_eq!(memory.len(), (1_000_000_000_000 / PAGE_SIZE + 1) * PAGE_SIZE);
+    }
+
+    #[test]
+    fn memory_len_saturates_on_last_page() {
+        let mut memory = Memory::new(vec![1, 2]);
+
+        memory.set(usize::MAX, 7);
+
+        assert_eq!(memory.get(usize::MAX), 7);
+        assert_eq!(memory.len(), usize::MAX);
+    }
+
+    #[test]
+    fn memory_grows_dense_near_image() {
+        let mut memory = Memory::new(vec![1, 2]);
+
+        memory.set(100, 7);
+
+        assert_eq!(memory.as_slice().len(), 101);
+        assert_eq!(memory.pages().count(), 0);
+    }
+}
@@ -0,0 +1,207 @@
+use std::{error::Error, fmt};
+
+use crate::{IntComp, error::IntCompError, instruction::Status};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug)]
+pub struct NetworkError {
+    pub machine: usize,
+    pub error: IntCompError,
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "machine {}: {}", self.machine, self.error)
+    }
+}
+
+impl Error for NetworkError {}
+
+#[derive(Debug)]
+struct Edge {
+    name: String,
+    from: usize,
+    to: Option<usize>,
+    values: Vec<i64>,
+}
+
+/// Set of machines whose outputs are wired into the inputs of other machines.
+#[derive(Default)]
+pub struct Network {
+    machines: Vec<IntComp>,
+    edges: Vec<Edge>,
+}
+
+impl Network {
+    pub fn new() -> Self {
+        Network::default()
+    }
+
+    pub fn add_machine(&mut self, machine: IntComp) -> usize {
+        self.machines.push(machine);
+        self.machines.len() - 1
+    }
+
+    pub fn machine(&self, id: usize) -> &IntComp {
+        &self.machines[id]
+    }
+
+    pub fn machine_mut(&mut self, id: usize) -> &mut IntComp {
+        &mut self.machines[id]
+    }
+
+    /// Routes every output of `from` into the input of `to`.
+    pub fn connect(&mut self, name: &str, from: usize, to: usize) {
+        self.add_edge(name, from, Some(to));
+    }
+
+    /// Records every output of `from` without feeding it to another machine.
+    pub fn output(&mut self, name: &str, from: usize) {
+        self.add_edge(name, from, None);
+    }
+
+    fn add_edge(&mut self, name: &str, from: usize, to: Option<usize>) {
+        assert!(from < self.machines.len(), "{} is not a machine", from);
+        if let Some(to) = to {
+            assert!(to < self.machines.len(), "{} is not a machine", to);
+        }
+
+        self.edges.push(Edge { name: name.to_string(), from, to, values: Vec::new() });
+    }
+
+    pub fn push_input(&mut self, machine: usize, input: i64) {
+        self.machines[machine].push_input(input);
+    }
+
+    pub fn values(&self, name: &str) -> Option<&[i64]> {
+        self.edges.iter().find(|edge| edge.name == name).map(|edge| edge.values.as_slice())
+    }
+
+    pub fn last_value(&self, name: &str) -> Option<i64> {
+        self.values(name)?.last().copied()
+    }
+
+    /// Runs the machines in turn until all of them halt, or until the ones still running all wait for input
+    /// nobody is going to send.
+    pub fn run(&mut self) -> Result<Status, NetworkError> {
+        loop {
+            let mut delivered = false;
+
+            for id in 0..self.machines.len() {
+                while let Status::Outputed(value) = self.machines[id].run()
+                    .map_err(|error| NetworkError { machine: id, error })? {
+                    self.send(id, value);
+                    delivered = true;
+                }
+            }
+
+            if self.machines.iter().all(|machine| machine.status() == Status::Halted) {
+                break Ok(Status::Halted);
+            }
+            if !delivered {
+                break Ok(Status::RequestedInput);
+            }
+        }
+    }
+
+    fn send(&mut self, from: usize, value: i64) {
+        for edge in self.edges.iter_mut().filter(|edge| edge.from == from) {
+            edge.values.push(value);
+            if let Some(to) = edge.to {
+                self.machines[to].push_input(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amplifiers(program: &[i64], phases: &[i64], feedback: bool) -> Network {
+        let mut network = Network::new();
+        for phase in phases {
+            let id = network.add_machine(IntComp::new(program));
+            network.push_input(id, *phase);
+        }
+
+        let last = phases.len() - 1;
+        for id in 0..last {
+            network.connect(&format!("{}->{}", id, id + 1), id, id + 1);
+        }
+        if feedback {
+            network.connect("thrusters", last, 0);
+        } else {
+            network.output("thrusters", last);
+        }
+        network.push_input(0, 0);
+
+        network
+    }
+
+    #[test]
+    fn network_runs_linear_chain() {
+        let program = vec![3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0];
+        let mut network = amplifiers(&program, &[4, 3, 2, 1, 0], false);
+
+        let status = network.run().unwrap();
+
+        assert_eq!(status, Status::Halted);
+        assert_eq!(network.last_value("thrusters"), Some(43210));
+    }
+
+    #[test]
+    fn network_runs_feedback_loop() {
+        let program = vec![3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,
+            27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5];
+        let mut network = amplifiers(&program, &[9, 8, 7, 6, 5], true);
+
+        let status = network.run().unwrap();
+
+        assert_eq!(status, Status::Halted);
+        assert_eq!(network.last_value("thrusters"), Some(139629729));
+    }
+
+    #[test]
+    fn network_fans_out() {
+        let double = vec![3, 9, 102, 2, 9, 9, 4, 9, 99, 0];
+        let negate = vec![3, 9, 102, -1, 9, 9, 4, 9, 99, 0];
+        let mut network = Network::new();
+        let source = network.add_machine(IntComp::new(&[104, 21, 99]));
+        let left = network.add_machine(IntComp::new(&double));
+        let right = network.add_machine(IntComp::new(&negate));
+        network.connect("left", source, left);
+        network.connect("right", source, right);
+        network.output("doubled", left);
+        network.output("negated", right);
+
+        let status = network.run().unwrap();
+
+        assert_eq!(status, Status::Halted);
+        assert_eq!(network.values("doubled"), Some(&[42][..]));
+        assert_eq!(network.values("negated"), Some(&[-21][..]));
+    }
+
+    #[test]
+    fn network_stops_when_starved() {
+        let mut network = Network::new();
+        let id = network.add_machine(IntComp::new(&[3, 3, 99, 0]));
+        network.output("out", id);
+
+        let status = network.run().unwrap();
+
+        assert_eq!(status, Status::RequestedInput);
+        assert_eq!(network.last_value("out"), None);
+    }
+
+    #[test]
+    fn network_reports_failing_machine() {
+        let mut network = Network::new();
+        network.add_machine(IntComp::new(&[99]));
+        network.add_machine(IntComp::new(&[42]));
+
+        let error = network.run().unwrap_err();
+
+        assert_eq!(error, NetworkError { machine: 1, error: IntCompError::UnknownOpcode { index: 0, word: 42 } });
+    }
+}
@@ -0,0 +1,57 @@
+use crate::word::Word;
+
+/// What `ADD` and `MUL` do when the result does not fit in a word.
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+#[derive(Debug)]
+pub enum Overflow {
+    /// Stop with `IntCompError::Overflow`.
+    #[default]
+    Error,
+    /// Wrap around in two's complement.
+    Wrap,
+    /// Clamp to the smallest or largest word.
+    Saturate,
+    /// Stop with `IntCompError::WordTooNarrow` before the instruction executes, so the host can
+    /// continue on a wider word type with `IntComp::promote` or `Snapshot::promote`.
+    Promote,
+}
+
+impl Overflow {
+    /// Returns `None` only under `Overflow::Error` and `Overflow::Promote`.
+    pub fn add<W: Word>(self, a: &W, b: &W) -> Option<W> {
+        match self {
+            Overflow::Error | Overflow::Promote => a.checked_add(b),
+            Overflow::Wrap => Some(a.wrapping_add(b)),
+            Overflow::Saturate => Some(a.saturating_add(b)),
+        }
+    }
+
+    pub fn mul<W: Word>(self, a: &W, b: &W) -> Option<W> {
+        match self {
+            Overflow::Error | Overflow::Promote => a.checked_mul(b),
+            Overflow::Wrap => Some(a.wrapping_mul(b)),
+            Overflow::Saturate => Some(a.saturating_mul(b)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow_policies_differ_only_on_overflow() {
+        for overflow in [Overflow::Error, Overflow::Wrap, Overflow::Saturate] {
+            assert_eq!(overflow.add(&2i64, &3), Some(5));
+            assert_eq!(overflow.mul(&-4i64, &3), Some(-12));
+        }
+
+        assert_eq!(Overflow::Error.add(&i64::MAX, &1), None);
+        assert_eq!(Overflow::Wrap.add(&i64::MAX, &1), Some(i64::MIN));
+        assert_eq!(Overflow::Saturate.add(&i64::MAX, &1), Some(i64::MAX));
+        assert_eq!(Overflow::Error.mul(&i64::MIN, &-1), None);
+        assert_eq!(Overflow::Wrap.mul(&i64::MIN, &-1), Some(i64::MIN));
+        assert_eq!(Overflow::Saturate.mul(&i64::MIN, &2), Some(i64::MIN));
+        assert_eq!(Overflow::Error.mul(&i128::from(i64::MAX), &2), Some(i128::from(i64::MAX) * 2));
+    }
+}
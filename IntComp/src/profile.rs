@@ -0,0 +1,169 @@
+use std::{collections::BTreeMap, io::{self, Write}};
+
+use crate::{disasm::disassemble_at, instruction::Instruction};
+
+/// How many rows of each table the text report shows.
+const REPORT_ROWS: usize = 20;
+
+/// Execution counts collected by `IntComp::enable_profiling`.
+#[derive(Clone, Default)]
+#[derive(Debug)]
+pub struct Profile {
+    executed: u64,
+    by_address: BTreeMap<usize, u64>,
+    by_instruction: BTreeMap<&'static str, u64>,
+    reads: BTreeMap<usize, u64>,
+    writes: BTreeMap<usize, u64>,
+    max_address: Option<usize>,
+}
+
+impl Profile {
+    pub(crate) fn record(&mut self, index: usize, instruction: &Instruction, reads: &[usize], write: Option<usize>) {
+        self.executed += 1;
+        *self.by_address.entry(index).or_default() += 1;
+        *self.by_instruction.entry(instruction.mnemonic()).or_default() += 1;
+
+        let param_count = instruction.opcode().map_or(0, |oc| oc.param_count as usize);
+        self.touch(index + param_count);
+        for &address in reads {
+            *self.reads.entry(address).or_default() += 1;
+            self.touch(address);
+        }
+        if let Some(address) = write {
+            *self.writes.entry(address).or_default() += 1;
+            self.touch(address);
+        }
+    }
+
+    fn touch(&mut self, address: usize) {
+        self.max_address = self.max_address.max(Some(address));
+    }
+
+    pub fn executed(&self) -> u64 {
+        self.executed
+    }
+
+    pub fn executions(&self, address: usize) -> u64 {
+        self.by_address.get(&address).copied().unwrap_or_default()
+    }
+
+    pub fn instruction_count(&self, mnemonic: &str) -> u64 {
+        self.by_instruction.get(mnemonic).copied().unwrap_or_default()
+    }
+
+    pub fn reads(&self, address: usize) -> u64 {
+        self.reads.get(&address).copied().unwrap_or_default()
+    }
+
+    pub fn writes(&self, address: usize) -> u64 {
+        self.writes.get(&address).copied().unwrap_or_default()
+    }
+
+    /// Highest address executed, read or written.
+    pub fn max_address(&self) -> Option<usize> {
+        self.max_address
+    }
+
+    /// Executed addresses, most executed first.
+    pub fn hot_spots(&self) -> Vec<(usize, u64)> {
+        sorted(&self.by_address)
+    }
+
+    /// Writes the hottest addresses with their disassembly, followed by instruction, read and write
+    /// counts. `program` is the memory used to disassemble the hot spots.
+    pub fn write_report(&self, program: &[i64], output: &mut impl Write) -> io::Result<()> {
+        let max_address = self.max_address.map_or("nothing".to_string(), |address| address.to_string());
+        writeln!(output, "executed {} instructions, touched memory up to {}", self.executed, max_address)?;
+
+        writeln!(output, "\nhot spots")?;
+        for (address, count) in self.hot_spots().into_iter().take(REPORT_ROWS) {
+            writeln!(output, "{:>10}  {:>5}: {}", count, address, disassemble_at(program, address).text)?;
+        }
+
+        writeln!(output, "\ninstructions")?;
+        for (mnemonic, count) in sorted(&self.by_instruction).into_iter().take(REPORT_ROWS) {
+            writeln!(output, "{:>10}  {}", count, mnemonic)?;
+        }
+
+        for (title, counts) in [("memory reads", &self.reads), ("memory writes", &self.writes)] {
+            writeln!(output, "\n{}", title)?;
+            for (address, count) in sorted(counts).into_iter().take(REPORT_ROWS) {
+                writeln!(output, "{:>10}  [{}]", count, address)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes every count as `kind,key,count,text` rows, sorted like the text report.
+    pub fn write_csv(&self, program: &[i64], output: &mut impl Write) -> io::Result<()> {
+        writeln!(output, "kind,key,count,text")?;
+        for (address, count) in self.hot_spots() {
+            writeln!(output, "address,{},{},\"{}\"", address, count, disassemble_at(program, address).text)?;
+        }
+        for (mnemonic, count) in sorted(&self.by_instruction) {
+            writeln!(output, "instruction,{},{},", mnemonic, count)?;
+        }
+        for (address, count) in sorted(&self.reads) {
+            writeln!(output, "read,{},{},", address, count)?;
+        }
+        for (address, count) in sorted(&self.writes) {
+            writeln!(output, "write,{},{},", address, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn sorted<K: Copy + Ord>(counts: &BTreeMap<K, u64>) -> Vec<(K, u64)> {
+    let mut counts: Vec<(K, u64)> = counts.iter().map(|(key, count)| (*key, *count)).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{IntComp, instruction::Status};
+
+    // Counts [9] down from 3 to 0.
+    const COUNTDOWN: [i64; 10] = [1001, 9, -1, 9, 1005, 9, 0, 99, 0, 3];
+
+    fn profiled() -> IntComp {
+        let mut int_comp = IntComp::new(&COUNTDOWN);
+        int_comp.enable_profiling();
+        assert_eq!(int_comp.run().unwrap(), Status::Halted);
+        int_comp
+    }
+
+    #[test]
+    fn profile_counts_executions_and_memory() {
+        let int_comp = profiled();
+        let profile = int_comp.profile().unwrap();
+
+        assert_eq!(profile.executed(), 7);
+        assert_eq!(profile.hot_spots(), vec![(0, 3), (4, 3), (7, 1)]);
+        assert_eq!(profile.instruction_count("JT"), 3);
+        assert_eq!(profile.instruction_count("HLT"), 1);
+        assert_eq!(profile.reads(9), 6);
+        assert_eq!(profile.writes(9), 3);
+        assert_eq!(profile.max_address(), Some(9));
+    }
+
+    #[test]
+    fn profile_reports_hot_spots_with_mnemonics() {
+        let int_comp = profiled();
+        let profile = int_comp.profile().unwrap();
+
+        let mut text = Vec::new();
+        profile.write_report(&int_comp.get_program(), &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.starts_with("executed 7 instructions, touched memory up to 9\n\nhot spots\n"));
+        assert!(text.contains("         3      0: ADD [9], #-1, [9]\n"));
+
+        let mut csv = Vec::new();
+        profile.write_csv(&int_comp.get_program(), &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("kind,key,count,text\naddress,0,3,\"ADD [9], #-1, [9]\"\n"));
+        assert!(csv.contains("\nread,9,6,\n"));
+    }
+}
@@ -0,0 +1,222 @@
+use std::{fs, io::{self, Read, Write}, path::Path};
+
+use crate::{Program, instruction::Status, memory::{Memory, PAGE_SIZE}, word::Word};
+
+const MAGIC: &[u8; 4] = b"ICS3";
+
+/// Full machine state captured by `IntComp::snapshot`.
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct Snapshot<W = i64> {
+    pub(crate) program: Program<W>,
+}
+
+impl<W: Word> Snapshot<W> {
+    pub fn memory(&self) -> &Memory<W> {
+        &self.program.memory
+    }
+
+    pub fn index(&self) -> usize {
+        self.program.index
+    }
+
+    pub fn status(&self) -> Status<W> {
+        self.program.status.clone()
+    }
+
+    /// Copies the snapshot onto the wider word type `V`, to restore into an `IntComp<V>`.
+    pub fn promote<V: Word + From<W>>(&self) -> Snapshot<V> {
+        Snapshot { program: self.program.promote() }
+    }
+}
+
+/// The binary format stores 64-bit words, so only `i64` snapshots can be saved.
+impl Snapshot {
+    /// Serializes the snapshot as little-endian words behind a short magic header. Far-out memory is
+    /// stored page by page, so sparse snapshots stay small.
+    pub fn write_to(&self, output: &mut impl Write) -> io::Result<()> {
+        let program = &self.program;
+        let (tag, value) = match program.status {
+            Status::Ready => (0, 0),
+            Status::RequestedInput => (1, 0),
+            Status::Outputed(value) => (2, value),
+            Status::Halted => (3, 0),
+            Status::BudgetExhausted => (4, 0),
+        };
+
+        output.write_all(MAGIC)?;
+        write_u64(output, program.index as u64)?;
+        write_i64(output, program.relative_base)?;
+        write_u64(output, tag)?;
+        write_i64(output, value)?;
+        write_u64(output, program.max_address.map_or(u64::MAX, |max_address| max_address as u64))?;
+        write_u64(output, program.executed)?;
+        write_words(output, program.input.iter())?;
+        write_u64(output, program.memory.dense_limit() as u64)?;
+        write_words(output, program.memory.as_slice().iter())?;
+
+        write_u64(output, program.memory.pages().count() as u64)?;
+        for (start, words) in program.memory.pages() {
+            write_u64(output, start as u64)?;
+            for word in words {
+                write_i64(output, *word)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from(input: &mut impl Read) -> io::Result<Snapshot> {
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not an IntComp snapshot"));
+        }
+
+        let index = read_u64(input)? as usize;
+        let relative_base = read_i64(input)?;
+        let tag = read_u64(input)?;
+        let value = read_i64(input)?;
+        let status = match tag {
+            0 => Status::Ready,
+            1 => Status::RequestedInput,
+            2 => Status::Outputed(value),
+            3 => Status::Halted,
+            4 => Status::BudgetExhausted,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a status", tag))),
+        };
+
+        let max_address = read_u64(input)?;
+        let executed = read_u64(input)?;
+        let pending_input = read_words(input)?;
+        let dense_limit = read_u64(input)? as usize;
+        let dense = read_words(input)?;
+
+        let mut pages = Vec::new();
+        for _ in 0..read_u64(input)? {
+            let start = read_u64(input)? as usize;
+            let words = (0..PAGE_SIZE).map(|_| read_i64(input)).collect::<io::Result<Vec<i64>>>()?;
+            pages.push((start, words));
+        }
+
+        let mut program = Program::new(Vec::new());
+        program.index = index;
+        program.relative_base = relative_base;
+        program.status = status;
+        program.max_address = (max_address != u64::MAX).then_some(max_address as usize);
+        program.executed = executed;
+        program.input = pending_input.into_iter().collect();
+        program.memory = Memory::from_parts(dense, dense_limit, pages)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "memory pages overlap"))?;
+
+        Ok(Snapshot { program })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        self.write_to(&mut file)?;
+        file.flush()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Snapshot> {
+        Snapshot::read_from(&mut io::BufReader::new(fs::File::open(path)?))
+    }
+}
+
+fn write_u64(output: &mut impl Write, value: u64) -> io::Result<()> {
+    output.write_all(&value.to_le_bytes())
+}
+
+fn write_i64(output: &mut impl Write, value: i64) -> io::Result<()> {
+    output.write_all(&value.to_le_bytes())
+}
+
+fn write_words<'a>(output: &mut impl Write, words: impl ExactSizeIterator<Item = &'a i64>) -> io::Result<()> {
+    write_u64(output, words.len() as u64)?;
+    for word in words {
+        write_i64(output, *word)?;
+    }
+
+    Ok(())
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_i64(input: &mut impl Read) -> io::Result<i64> {
+    let mut bytes = [0u8; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(i64::from_le_bytes(bytes))
+}
+
+fn read_words(input: &mut impl Read) -> io::Result<Vec<i64>> {
+    let len = read_u64(input)?;
+    (0..len).map(|_| read_i64(input)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntComp;
+
+    // Outputs twice its input and halts.
+    const DOUBLE: [i64; 10] = [3, 9, 1002, 9, 2, 9, 4, 9, 99, 0];
+
+    #[test]
+    fn restore_returns_to_snapshot() {
+        let mut int_comp = IntComp::new(&DOUBLE);
+        int_comp.run().unwrap();
+        let snapshot = int_comp.snapshot();
+
+        for input in 1..4 {
+            int_comp.restore(&snapshot);
+            assert_eq!(int_comp.run_with_input(input).unwrap(), Status::Outputed(input * 2));
+        }
+        assert_eq!(snapshot.status(), Status::RequestedInput);
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_bytes() {
+        let mut int_comp = IntComp::new(&DOUBLE);
+        int_comp.run_with_input(21).unwrap();
+        int_comp.push_input(5);
+        let snapshot = int_comp.snapshot();
+
+        let mut bytes = Vec::new();
+        snapshot.write_to(&mut bytes).unwrap();
+        let loaded = Snapshot::read_from(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(loaded.memory().as_slice(), snapshot.memory().as_slice());
+        assert_eq!(loaded.index(), 8);
+        assert_eq!(loaded.status(), Status::Outputed(42));
+        assert_eq!(loaded.program.input, vec![5]);
+        assert_eq!(loaded.program.executed, 3);
+    }
+
+    #[test]
+    fn snapshot_round_trips_far_memory() {
+        let mut int_comp = IntComp::new(&DOUBLE);
+        int_comp.poke(60000, 8);
+        int_comp.poke(1 << 40, 9);
+        int_comp.set_max_address(Some(1 << 41));
+
+        let mut bytes = Vec::new();
+        int_comp.snapshot().write_to(&mut bytes).unwrap();
+        let loaded = Snapshot::read_from(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(loaded.memory().get(1 << 40), 9);
+        assert_eq!(loaded.memory().get(60000), 8);
+        assert_eq!(loaded.memory().as_slice()[..10], DOUBLE);
+        assert_eq!(loaded.program.max_address, Some(1 << 41));
+    }
+
+    #[test]
+    fn read_from_rejects_garbage() {
+        let error = Snapshot::read_from(&mut "nope, not a snapshot".as_bytes()).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,135 @@
+use std::io::{self, Write};
+
+use crate::{instruction::{Instruction, param::{Param, ParamMode}}, word::Word};
+
+pub struct TraceEvent<'a, W = i64> {
+    pub index: usize,
+    pub instruction: &'a Instruction,
+    pub params: &'a [Param<W>],
+    /// Value of every parameter as read before the instruction executed.
+    pub operands: &'a [W],
+    pub write: Option<(usize, W)>,
+}
+
+pub trait Tracer<W = i64> {
+    fn trace(&mut self, event: &TraceEvent<W>);
+
+    /// The I/O error that stopped the tracer, for tracers that write somewhere.
+    fn error(&self) -> Option<&io::Error> {
+        None
+    }
+}
+
+impl<W, F: FnMut(&TraceEvent<W>)> Tracer<W> for F {
+    fn trace(&mut self, event: &TraceEvent<W>) {
+        self(event)
+    }
+}
+
+/// Writes one line per executed instruction, e.g. `   12: ADD [20]=3, #-1, [20]=3 -> [20]=2`.
+/// Stops writing at the first I/O error, which is kept for `error`.
+pub struct WriteTracer<W: Write> {
+    output: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> WriteTracer<W> {
+    pub fn new(output: W) -> Self {
+        WriteTracer { output, error: None }
+    }
+
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+impl<W: Write, V: Word> Tracer<V> for WriteTracer<W> {
+    fn trace(&mut self, event: &TraceEvent<V>) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let operands: Vec<String> = event.params.iter().zip(event.operands)
+            .map(|(param, value)| match param.config {
+                ParamMode::Immediate => param.to_string(),
+                _ => format!("{}={}", param, value),
+            })
+            .collect();
+
+        let mut line = format!("{:>5}: {} {}", event.index, event.instruction.mnemonic(), operands.join(", "));
+        if let Some((address, value)) = &event.write {
+            line.push_str(&format!(" -> [{}]={}", address, value));
+        }
+
+        if let Err(error) = writeln!(self.output, "{}", line.trim_end()) {
+            self.error = Some(error);
+        }
+    }
+
+    fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntComp;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_tracer_logs_instructions() {
+        let buffer = SharedBuffer::default();
+        let mut int_comp = IntComp::new(&[1001, 7, -1, 7, 4, 7, 99, 3]);
+        int_comp.set_tracer(WriteTracer::new(buffer.clone()));
+
+        int_comp.run().unwrap();
+        int_comp.run().unwrap();
+
+        let log = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert_eq!(log, "    0: ADD [7]=3, #-1, [7]=3 -> [7]=2\n    4: OUT [7]=2\n    6: HLT\n");
+    }
+
+    #[test]
+    fn closure_tracer_sees_writes() {
+        let writes = Rc::new(RefCell::new(Vec::new()));
+        let seen = writes.clone();
+        let mut int_comp = IntComp::new(&[3, 5, 99, 0, 0, 0]);
+        int_comp.set_tracer(move |event: &TraceEvent| seen.borrow_mut().push((event.index, event.write)));
+
+        int_comp.run_with_input(8).unwrap();
+
+        assert_eq!(*writes.borrow(), vec![(0, Some((5, 8))), (2, None)]);
+    }
+
+    #[test]
+    fn write_tracer_keeps_first_error() {
+        let event = TraceEvent::<i64> { index: 6, instruction: &Instruction::Halt, params: &[], operands: &[], write: None };
+        let mut output = [0u8; 8];
+        let mut tracer = WriteTracer::new(&mut output[..]);
+
+        tracer.trace(&event);
+        tracer.trace(&event);
+
+        assert!(Tracer::<i64>::error(&tracer).is_some());
+        assert_eq!(tracer.take_error().unwrap().kind(), std::io::ErrorKind::WriteZero);
+        assert_eq!(&output, b"    6: H");
+    }
+}
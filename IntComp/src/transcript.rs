@@ -0,0 +1,210 @@
+use std::{error::Error, fmt, fs, io::{self, BufRead, Write}, path::Path};
+
+use crate::{IntComp, error::IntCompError, instruction::Status, word::Word};
+
+/// One value crossing the machine boundary. `executed` counts the instructions executed before
+/// it, `index` is the address of the instruction that read or wrote the value.
+#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug)]
+pub enum TranscriptEvent<W = i64> {
+    Input { executed: u64, index: usize, value: W },
+    Output { executed: u64, index: usize, value: W },
+}
+
+impl<W: Word> TranscriptEvent<W> {
+    pub fn index(&self) -> usize {
+        match self {
+            TranscriptEvent::Input { index, .. } | TranscriptEvent::Output { index, .. } => *index,
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for TranscriptEvent<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptEvent::Input { executed, index, value } => write!(f, "in {} {} {}", executed, index, value),
+            TranscriptEvent::Output { executed, index, value } => write!(f, "out {} {} {}", executed, index, value),
+        }
+    }
+}
+
+/// Every input and output of a session, recorded by `IntComp::start_recording`. Saved as one
+/// event per line followed by `end <executed>`.
+#[derive(PartialEq, Eq, Clone, Default)]
+#[derive(Debug)]
+pub struct Transcript<W = i64> {
+    pub events: Vec<TranscriptEvent<W>>,
+    /// Instructions executed when the recording was taken.
+    pub executed: u64,
+}
+
+impl<W: Word> Transcript<W> {
+    pub fn inputs(&self) -> impl Iterator<Item = &W> {
+        self.events.iter().filter_map(|event| match event {
+            TranscriptEvent::Input { value, .. } => Some(value),
+            TranscriptEvent::Output { .. } => None,
+        })
+    }
+
+    pub fn write_to(&self, output: &mut impl Write) -> io::Result<()> {
+        for event in &self.events {
+            writeln!(output, "{}", event)?;
+        }
+        writeln!(output, "end {}", self.executed)
+    }
+
+    pub fn read_from(input: impl BufRead) -> io::Result<Self> {
+        let mut transcript = Transcript::default();
+        for (line_index, line) in input.lines().enumerate() {
+            let line = line?;
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("line {}: cannot read '{}'", line_index + 1, line));
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |field: usize| fields.get(field).and_then(|field| field.parse().ok()).ok_or_else(invalid);
+            let value = || fields.get(3).and_then(|field| field.parse().ok()).ok_or_else(invalid);
+
+            match fields.first().copied() {
+                None => continue,
+                Some("in") => transcript.events.push(TranscriptEvent::Input {
+                    executed: number(1)?, index: number(2)? as usize, value: value()? }),
+                Some("out") => transcript.events.push(TranscriptEvent::Output {
+                    executed: number(1)?, index: number(2)? as usize, value: value()? }),
+                Some("end") => transcript.executed = number(1)?,
+                Some(_) => return Err(invalid()),
+            }
+        }
+
+        Ok(transcript)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        self.write_to(&mut file)?;
+        file.flush()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Transcript::read_from(io::BufReader::new(fs::File::open(path)?))
+    }
+}
+
+/// Where a replay stopped matching its transcript.
+#[derive(PartialEq, Clone)]
+#[derive(Debug)]
+pub struct Divergence<W = i64> {
+    /// Position of the first mismatching event in the transcript.
+    pub event: usize,
+    /// Instruction pointer where the replay diverged.
+    pub index: usize,
+    pub expected: Option<TranscriptEvent<W>>,
+    pub found: Option<TranscriptEvent<W>>,
+    pub error: Option<IntCompError>,
+}
+
+impl<W: Word> fmt::Display for Divergence<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |event: &Option<TranscriptEvent<W>>| event.as_ref().map_or("nothing".to_string(), |event| event.to_string());
+        write!(f, "event {} diverged at {}: expected {}, found {}", self.event, self.index, describe(&self.expected), describe(&self.found))?;
+        if let Some(error) = &self.error {
+            write!(f, " ({})", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Word> Error for Divergence<W> {}
+
+/// Feeds the recorded inputs to a freshly loaded machine and checks it reads and writes exactly
+/// what the transcript says, stopping after as many instructions as the recorded session ran.
+pub fn replay<W: Word>(int_comp: &mut IntComp<W>, transcript: &Transcript<W>) -> Result<Status<W>, Divergence<W>> {
+    int_comp.start_recording();
+    int_comp.extend_input(transcript.inputs().cloned());
+
+    let mut checked = 0;
+    loop {
+        let remaining = transcript.executed.saturating_sub(int_comp.executed());
+        let result = int_comp.run_for(remaining);
+
+        let found = &int_comp.transcript().expect("recording is on").events;
+        for (event, found) in found.iter().enumerate().skip(checked) {
+            let expected = transcript.events.get(event);
+            if expected != Some(found) {
+                return Err(Divergence { event, index: found.index(), expected: expected.cloned(), found: Some(found.clone()), error: None });
+            }
+        }
+        checked = found.len();
+
+        match result {
+            Ok(Status::Outputed(_)) => continue,
+            Ok(status) if checked == transcript.events.len() => return Ok(status),
+            result => return Err(Divergence {
+                event: checked,
+                index: int_comp.index(),
+                expected: transcript.events.get(checked).cloned(),
+                found: None,
+                error: result.err(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Outputs each input plus one until it reads 0.
+    const INCREMENT: [i64; 16] = [3, 15, 1006, 15, 14, 1001, 15, 1, 15, 4, 15, 1105, 1, 0, 99, 0];
+
+    fn program() -> Vec<i64> {
+        INCREMENT.to_vec()
+    }
+
+    fn recorded() -> Transcript {
+        let mut int_comp = IntComp::new(&program());
+        int_comp.start_recording();
+        int_comp.extend_input([4, 7, 0]);
+        while int_comp.run().unwrap() != Status::Halted {}
+
+        int_comp.take_transcript().unwrap()
+    }
+
+    #[test]
+    fn records_inputs_and_outputs() {
+        let transcript = recorded();
+
+        assert_eq!(transcript.events[0], TranscriptEvent::Input { executed: 0, index: 0, value: 4 });
+        assert_eq!(transcript.events[1], TranscriptEvent::Output { executed: 3, index: 9, value: 5 });
+        assert_eq!(transcript.events.len(), 5);
+        assert_eq!(transcript.inputs().copied().collect::<Vec<i64>>(), vec![4, 7, 0]);
+    }
+
+    #[test]
+    fn transcript_round_trips_through_text() {
+        let transcript = recorded();
+
+        let mut text = Vec::new();
+        transcript.write_to(&mut text).unwrap();
+
+        assert!(text.starts_with(b"in 0 0 4\nout 3 9 5\n"));
+        assert_eq!(Transcript::read_from(text.as_slice()).unwrap(), transcript);
+    }
+
+    #[test]
+    fn replay_matches_recording() {
+        let transcript = recorded();
+
+        assert_eq!(replay(&mut IntComp::new(&program()), &transcript), Ok(Status::Halted));
+    }
+
+    #[test]
+    fn replay_reports_first_divergence() {
+        let transcript = recorded();
+        let mut patched = program();
+        patched[7] = 2;
+
+        let divergence = replay(&mut IntComp::new(&patched), &transcript).unwrap_err();
+
+        assert_eq!(divergence.event, 1);
+        assert_eq!(divergence.index, 9);
+        assert_eq!(divergence.found, Some(TranscriptEvent::Output { executed: 3, index: 9, value: 6 }));
+    }
+}
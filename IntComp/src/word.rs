@@ -0,0 +1,112 @@
+use std::{fmt, str::FromStr};
+
+/// A memory cell of the machine. Addresses, opcodes and relative base adjustments must still fit
+/// in an `i64`; only the values computed and stored by programs use the full width.
+pub trait Word: Clone + Default + PartialEq + PartialOrd + fmt::Debug + fmt::Display + FromStr + 'static {
+    fn from_i64(value: i64) -> Self;
+    fn to_i64(&self) -> Option<i64>;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    fn wrapping_add(&self, other: &Self) -> Self;
+    fn wrapping_mul(&self, other: &Self) -> Self;
+    fn saturating_add(&self, other: &Self) -> Self;
+    fn saturating_mul(&self, other: &Self) -> Self;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The value as an `i64`, clamped to its range. Used where errors report a word.
+    fn clamp_i64(&self) -> i64 {
+        self.to_i64().unwrap_or(if *self < Self::default() { i64::MIN } else { i64::MAX })
+    }
+}
+
+macro_rules! primitive_word {
+    ($($t:ty),*) => {$(
+        impl Word for $t {
+            fn from_i64(value: i64) -> Self {
+                value as $t
+            }
+
+            fn to_i64(&self) -> Option<i64> {
+                i64::try_from(*self).ok()
+            }
+
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
+
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *other)
+            }
+
+            fn wrapping_add(&self, other: &Self) -> Self {
+                <$t>::wrapping_add(*self, *other)
+            }
+
+            fn wrapping_mul(&self, other: &Self) -> Self {
+                <$t>::wrapping_mul(*self, *other)
+            }
+
+            fn saturating_add(&self, other: &Self) -> Self {
+                <$t>::saturating_add(*self, *other)
+            }
+
+            fn saturating_mul(&self, other: &Self) -> Self {
+                <$t>::saturating_mul(*self, *other)
+            }
+        }
+    )*};
+}
+
+primitive_word!(i64, i128);
+
+/// Arbitrary precision words never overflow, so every policy gives the exact result.
+#[cfg(feature = "bigint")]
+impl Word for num_bigint::BigInt {
+    fn from_i64(value: i64) -> Self {
+        value.into()
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        i64::try_from(self).ok()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn wrapping_add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn wrapping_mul(&self, other: &Self) -> Self {
+        self * other
+    }
+
+    fn saturating_add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn saturating_mul(&self, other: &Self) -> Self {
+        self * other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn i128_converts_to_i64_only_in_range() {
+        assert_eq!(Word::to_i64(&42i128), Some(42));
+        assert_eq!(Word::to_i64(&(1i128 << 70)), None);
+        assert_eq!((1i128 << 70).clamp_i64(), i64::MAX);
+        assert_eq!((-1i128 << 70).clamp_i64(), i64::MIN);
+    }
+}
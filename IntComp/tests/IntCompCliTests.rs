@@ -0,0 +1,142 @@
+#![allow(non_snake_case)]
+
+use std::{env, fs, io::Write, ops::Deref, path::{Path, PathBuf}, process::{Command, Output, Stdio}};
+
+/// A file in the temp directory that is removed when the test is done with it.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        TempFile(env::temp_dir().join(format!("intcomp-cli-{}-{}", std::process::id(), name)))
+    }
+}
+
+impl Deref for TempFile {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn write_program(name: &str, source: &str) -> TempFile {
+    let file = TempFile::new(name);
+    fs::write(&file.0, source).unwrap();
+    file
+}
+
+fn intcomp(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_intcomp"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn run_prints_outputs_for_argument_and_stdin_inputs() {
+    // Outputs twice each of its two inputs.
+    let path = write_program("double", "3,17,1002,17,2,17,4,17,3,17,1002,17,2,17,4,17,99,0");
+    let path = path.to_str().unwrap();
+
+    let output = intcomp(&["run", path, "4", "--stdin"], "5\n");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "8\n10\n");
+}
+
+#[test]
+fn run_reports_missing_input() {
+    let path = write_program("starved", "3,0,99");
+
+    let output = intcomp(&["run", path.to_str().unwrap()], "");
+
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn run_prints_ascii_and_peeks() {
+    let path = write_program("ascii", "104,72,104,105,104,10,104,500,1,0,0,0,99");
+
+    let output = intcomp(&["run", path.to_str().unwrap(), "--ascii", "--peek", "0"], "");
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Hi\n500\n[0] = 208\n");
+}
+
+#[test]
+fn run_sends_stdin_lines_as_ascii() {
+    // Echoes two characters.
+    let path = write_program("echo", "3,10,4,10,3,10,4,10,99,0,0");
+
+    let output = intcomp(&["run", path.to_str().unwrap(), "--ascii", "--stdin"], "a\n");
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "a\n");
+}
+
+#[test]
+fn replay_checks_recorded_session() {
+    let path = write_program("record", "3,9,1001,9,1,9,4,9,99,0");
+    let path = path.to_str().unwrap();
+    let transcript = TempFile::new("transcript");
+    let transcript = transcript.to_str().unwrap();
+
+    let output = intcomp(&["run", path, "--stdin", "--record", transcript], "41\n");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "42\n");
+
+    assert_eq!(intcomp(&["replay", path, transcript], "").status.code(), Some(0));
+    let output = intcomp(&["replay", path, transcript, "--patch", "5=2"], "");
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("diverged at 6"));
+}
+
+#[test]
+fn patch_replaces_cells() {
+    let path = write_program("patch", "1,0,0,0,99");
+
+    let output = intcomp(&["patch", path.to_str().unwrap(), "1=4", "2=4"], "");
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1,4,4,0,99\n");
+}
+
+#[test]
+fn patch_checks_far_addresses() {
+    let path = write_program("far", "99");
+    let path = path.to_str().unwrap();
+
+    assert_eq!(intcomp(&["patch", path, "1000000000000=1"], "").status.code(), Some(2));
+    assert_eq!(intcomp(&["disasm", path, "--patch", "18446744073709551615=1"], "").status.code(), Some(2));
+
+    let output = intcomp(&["run", path, "--patch", "18446744073709551615=1", "--peek", "18446744073709551615"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "[18446744073709551615] = 1\n");
+}
+
+#[test]
+fn lint_reports_problems() {
+    let path = write_program("lint", "11101,1,2,3,3,1,99");
+
+    let output = intcomp(&["lint", path.to_str().unwrap()], "");
+
+    assert_eq!(output.status.code(), Some(6));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(),
+        "0: writes to immediate parameter\n4: writes [1] inside the instruction at 0\n");
+}
+
+#[test]
+fn exits_with_error_codes() {
+    let path = write_program("unknown", "42");
+
+    assert_eq!(intcomp(&["run", path.to_str().unwrap()], "").status.code(), Some(1));
+    assert_eq!(intcomp(&["frobnicate"], "").status.code(), Some(2));
+    assert_eq!(intcomp(&["run", "/nonexistent/program"], "").status.code(), Some(3));
+}
@@ -1,53 +1,96 @@
+#![allow(non_snake_case)]
+
 use IntComp::IntComp;
 use ::IntComp::instruction::Status;
+use ::IntComp::io::QueueIo;
+use ::IntComp::asm::assemble;
 
 #[test]
 fn self_replicating_program() {
     let program = vec![109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99];
-    let mut output = Vec::new();
+    let mut io = QueueIo::default();
     let mut int_comp = IntComp::new(&program);
-    let status: Status = loop {
-        let status = int_comp.run();
-
-        match status {
-            Status::Outputed(value) => output.push(value),
-            _ => break status
-        };
-    };
+    let status = int_comp.run_with_io(&mut io).unwrap();
+    let output = Vec::from(io.output);
 
+    assert_eq!(status, Status::Halted);
     assert_eq!(output, program);
 }
 
 #[test]
 fn big_number_from_the_middle() {
     let program = vec![104,1125899906842624i64,99];
-    let mut output = Vec::new();
+    let mut io = QueueIo::default();
     let mut int_comp = IntComp::new(&program);
-    let status: Status = loop {
-        let status = int_comp.run();
-
-        match status {
-            Status::Outputed(value) => output.push(value),
-            _ => break status
-        };
-    };
+    let status = int_comp.run_with_io(&mut io).unwrap();
+    let output = Vec::from(io.output);
 
+    assert_eq!(status, Status::Halted);
     assert_eq!(output[0], 1125899906842624i64);
 }
 
 #[test]
 fn outputs_16_digit_number() {
     let program = vec![1102,34915192,34915192,7,4,7,99,0];
-    let mut output = Vec::new();
+    let mut io = QueueIo::default();
+    let mut int_comp = IntComp::new(&program);
+    let status = int_comp.run_with_io(&mut io).unwrap();
+    let output = Vec::from(io.output);
+
+    assert_eq!(status, Status::Halted);
+    assert_eq!(output[0].to_string().len(), 16);
+}
+
+#[test]
+fn self_replicating_program_from_source() {
+    let source = "
+        .equ counter 100
+        .equ flag 101
+        copy:   ARB #1
+                OUT rb-1
+                ADD [counter], #1, [counter]
+                EQ [counter], #16, [flag]
+                JF [flag], #copy
+                HLT
+    ";
+    let program = assemble(source).unwrap();
+    let mut io = QueueIo::default();
+    let mut int_comp = IntComp::new(&program);
+    let status = int_comp.run_with_io(&mut io).unwrap();
+    let output = Vec::from(io.output);
+
+    assert_eq!(program, vec![109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99]);
+    assert_eq!(status, Status::Halted);
+    assert_eq!(output, program);
+}
+
+#[test]
+fn big_number_from_the_middle_from_source() {
+    let program = assemble("OUT #1125899906842624\nHLT").unwrap();
+    let mut io = QueueIo::default();
     let mut int_comp = IntComp::new(&program);
-    let status: Status = loop {
-        let status = int_comp.run();
+    let status = int_comp.run_with_io(&mut io).unwrap();
+    let output = Vec::from(io.output);
 
-        match status {
-            Status::Outputed(value) => output.push(value),
-            _ => break status
-        };
-    };
+    assert_eq!(status, Status::Halted);
+    assert_eq!(output[0], 1125899906842624i64);
+}
+
+#[test]
+fn outputs_16_digit_number_from_source() {
+    let source = "
+                MUL #34915192, #34915192, [result]
+                OUT [result]
+                HLT
+        result: .data 0
+    ";
+    let program = assemble(source).unwrap();
+    let mut io = QueueIo::default();
+    let mut int_comp = IntComp::new(&program);
+    let status = int_comp.run_with_io(&mut io).unwrap();
+    let output = Vec::from(io.output);
 
+    assert_eq!(program, vec![1102,34915192,34915192,7,4,7,99,0]);
+    assert_eq!(status, Status::Halted);
     assert_eq!(output[0].to_string().len(), 16);
 }
@@ -1,9 +1,8 @@
-use int_comp::IntComp;
-use int_comp;
-use int_comp::instruction::Status;
+use ::IntComp::IntComp;
+use ::IntComp::network::Network;
 
 fn main() {
-    let program: Vec<i32> = int_comp::get_file().unwrap();
+    let program: Vec<i64> = ::IntComp::get_program_from_file().unwrap();
 
     let result = get_thruster_value_v1(&program);
 
@@ -14,8 +13,8 @@ fn main() {
     println!("Biggest output: {}, biggest phases: {:?}", result.0, result.1);
 }
 
-fn get_thruster_value_v2(program: &Vec<i32>) -> (i32, Vec<i32>) {
-    let mut aggregate: Vec<Vec<i32>> = Vec::new();
+fn get_thruster_value_v2(program: &[i64]) -> (i64, Vec<i64>) {
+    let mut aggregate: Vec<Vec<i64>> = Vec::new();
     let mut permutable = vec![5, 6, 7, 8, 9];
     let k = permutable.len();
     generate_permutation(&mut aggregate, k, &mut permutable);
@@ -23,8 +22,7 @@ fn get_thruster_value_v2(program: &Vec<i32>) -> (i32, Vec<i32>) {
     let mut biggest_output = 0;
     let mut biggest_phases = permutable;
     for phases in aggregate {
-        let mut output = 0;
-        output = test_phases(program, &phases);
+        let output = test_phases(program, &phases, true);
         if output > biggest_output {
             biggest_output = output;
             biggest_phases = phases.clone();
@@ -34,67 +32,45 @@ fn get_thruster_value_v2(program: &Vec<i32>) -> (i32, Vec<i32>) {
     (biggest_output, biggest_phases)
 }
 
-fn test_phases(program: &Vec<i32>, phases: &Vec<i32>) -> i32 {
-    let mut comp: Vec<IntComp> = vec![
-        IntComp::new(program),
-        IntComp::new(program),
-        IntComp::new(program),
-        IntComp::new(program),
-        IntComp::new(program)
-    ];
-
-    for (i, phase) in phases.iter().enumerate() {
-        comp[i].run();
-        let status = comp[i].run_with_input(*phase);
-    }
+fn test_phases(program: &[i64], phases: &[i64], feedback: bool) -> i64 {
+    let mut network = Network::new();
+    let amplifiers: Vec<usize> = phases.iter().map(|phase| {
+        let id = network.add_machine(IntComp::new(program));
+        network.push_input(id, *phase);
+        id
+    }).collect();
 
-    let mut input = 0;
+    for pair in amplifiers.windows(2) {
+        network.connect(&format!("{}->{}", pair[0], pair[1]), pair[0], pair[1]);
+    }
 
-    'main: loop {
-        for c in comp.iter_mut() {
-            let status = c.run_with_input(input);
-            match status {
-                Status::Outputed(output) => { input = output; c.run(); continue; },
-                _ => break 'main
-            }
-            //println!("Input {}", input);
-        }
+    let first = amplifiers[0];
+    let last = amplifiers[amplifiers.len() - 1];
+    if feedback {
+        network.connect("thrusters", last, first);
+    } else {
+        network.output("thrusters", last);
     }
-    input
+
+    network.push_input(first, 0);
+    network.run().expect("amplifier failed");
+
+    network.last_value("thrusters").unwrap_or_default()
 }
 
-fn get_thruster_value_v1(program: &Vec<i32>) -> (i32, Vec<i32>) {
-    let mut aggregate: Vec<Vec<i32>> = Vec::new();
+fn get_thruster_value_v1(program: &[i64]) -> (i64, Vec<i64>) {
+    let mut aggregate: Vec<Vec<i64>> = Vec::new();
     let mut permutable = vec![0, 1, 2, 3, 4];
     let k = permutable.len();
-    
-    let mut comp: Vec<IntComp> = vec![
-        IntComp::new(program),
-        IntComp::new(program),
-        IntComp::new(program),
-        IntComp::new(program),
-        IntComp::new(program)
-    ];
     generate_permutation(&mut aggregate, k, &mut permutable);
 
     let mut biggest_output = 0;
     let mut biggest_phases = permutable;
 
     for phases in aggregate {
-        let mut input = 0;
-        for (i, phase) in phases.iter().enumerate() {
-            comp[i].reset();
-            comp[i].run();
-            comp[i].run_with_input(*phase);
-            let status = comp[i].run_with_input(input);
-            
-            match status {
-                Status::Outputed(output) => input = output,
-                _ => continue
-            }
-        }
-        if input > biggest_output {
-            biggest_output = input;
+        let output = test_phases(program, &phases, false);
+        if output > biggest_output {
+            biggest_output = output;
             biggest_phases = phases.clone();
         }
     }
@@ -102,7 +78,7 @@ fn get_thruster_value_v1(program: &Vec<i32>) -> (i32, Vec<i32>) {
     (biggest_output, biggest_phases)
 }
 
-fn generate_permutation(aggregate : &mut Vec<Vec<i32>>, k : usize, permutable: &mut Vec<i32>){
+fn generate_permutation(aggregate : &mut Vec<Vec<i64>>, k : usize, permutable: &mut Vec<i64>){
     if k == 1 {
         aggregate.push(permutable.clone());
         return;
@@ -127,7 +103,7 @@ mod test {
 
     #[test]
     fn generate_permutation_outputs_permutations() {
-        let mut aggregate: Vec<Vec<i32>> = Vec::new();
+        let mut aggregate: Vec<Vec<i64>> = Vec::new();
         let mut permutable = vec![1, 2, 3];
         let k = permutable.len();
         generate_permutation(&mut aggregate, k, &mut permutable);
@@ -137,7 +113,7 @@ mod test {
 
     #[test]
     fn get_thruster_value_test_1(){
-        let program: Vec<i32> = vec![3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0];
+        let program: Vec<i64> = vec![3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0];
         let expected_output = 43210;
         let expected_phases = vec![4,3,2,1,0];
 
@@ -149,7 +125,7 @@ mod test {
 
     #[test]
     fn get_thruster_value_test_2(){
-        let program: Vec<i32> = vec![3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0];
+        let program: Vec<i64> = vec![3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0];
         let expected_output = 54321;
         let expected_phases = vec![0,1,2,3,4];
 
@@ -161,7 +137,7 @@ mod test {
 
     #[test]
     fn get_thruster_value_test_3(){
-        let program: Vec<i32> = vec![3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,
+        let program: Vec<i64> = vec![3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,
         1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0];
         let expected_output = 65210;
         let expected_phases = vec![1,0,4,3,2];
@@ -171,4 +147,17 @@ mod test {
         assert_eq!(result.0, expected_output);
         assert_eq!(result.1, expected_phases);
     }
+
+    #[test]
+    fn get_thruster_value_feedback_test_1(){
+        let program: Vec<i64> = vec![3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,
+        27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5];
+        let expected_output = 139629729;
+        let expected_phases = vec![9,8,7,6,5];
+
+        let result = get_thruster_value_v2(&program);
+
+        assert_eq!(result.0, expected_output);
+        assert_eq!(result.1, expected_phases);
+    }
 }
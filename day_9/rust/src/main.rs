@@ -1,28 +1,35 @@
-use IntComp::{self, instruction::Status};
-use std::io::{ self, Read, BufRead };
+use IntComp::{self, ascii::AsciiComp, io::FnIo, debugger::Debugger};
+use std::{env, io::{ self, BufRead }};
 
 fn main() {
     let program = IntComp::get_program_from_file().unwrap();
 
     let mut int_comp = IntComp::IntComp::new(&program);
 
-    loop {
-        let status = int_comp.run();
+    if env::args().nth(2).as_deref() == Some("--debug") {
+        let mut debugger = Debugger::new(int_comp);
+        debugger.repl(io::stdin().lock(), io::stdout()).unwrap();
+        return;
+    }
+
+    if env::args().nth(2).as_deref() == Some("--ascii") {
+        AsciiComp::new(int_comp).interact(io::stdin().lock(), io::stdout()).unwrap();
+        return;
+    }
+
+    let mut io = FnIo::new(|| {
+        println!("Input requested");
+        let mut buffer = String::new();
+        let stdin = io::stdin();
+        let mut handle = stdin.lock();
 
-        match status {
-            Status::Outputed(number) => println!("Output: {}", number),
-            Status::RequestedInput => {
-                println!("Input requested");
-                let mut buffer = String::new();
-                let stdin = io::stdin();
-                let mut handle = stdin.lock();
+        handle.read_line(&mut buffer).unwrap();
+        let value: i64 = buffer.trim().parse().unwrap();
+        println!("Supplied: {}", value);
+        Some(value)
+    }, |number| println!("Output: {}", number));
 
-                handle.read_line(&mut buffer).unwrap();
-                let value: i64 = buffer.trim().parse().unwrap();
-                println!("Supplied: {}", value);
-                int_comp.run_with_input(value);
-            },
-            _ => break
-        }
+    if let Err(error) = int_comp.run_with_io(&mut io) {
+        println!("Error: {}", error);
     }
 }